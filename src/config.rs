@@ -85,14 +85,47 @@ pub struct Config {
     pub appearance_delay: usize,
     pub entries_around_cursor: usize,
     pub group_entries_while_unselected: usize,
+    // results a group loads before the rest wait behind a "show more" row, 0 loads everything
+    pub results_per_plugin: usize,
     pub total_search_delay: usize,
+    pub auto_delay_min: u32,
+    pub auto_delay_max: u32,
+    pub search_timeout: usize,
+    pub worker_threads: usize,
+    pub cache_size: usize,
     // pub show_countdown: bool,
     // pub flash_taskbar: bool,
     pub gap_between_search_bar_and_results: f32,
+    pub bar_anchor: BarAnchor,
+    // how far the bar's center is moved from the middle of the screen with BarAnchor::Custom, in points
+    pub bar_offset_x: f32,
+    pub bar_offset_y: f32,
+    // 0 sizes the bar and results to their contents
+    pub bar_width: f32,
+    // 0 lets the results fill the space between the bar and the edge of the screen
+    pub results_max_height: f32,
+    pub results_position: ResultsPosition,
     pub timezone: chrono_tz::Tz,
     pub chrono_format_string: String,
     pub time_font_size: f32,
     pub clock_enabled: bool,
+    pub show_icons: bool,
+    pub display_mode: DisplayMode,
+    pub preview_placement: PreviewPlacement,
+    pub monitor_placement: MonitorPlacement,
+    // only used with MonitorPlacement::Named, falls back to the primary monitor if no monitor has this name
+    pub monitor_name: String,
+    pub preview_delay: usize,
+    pub frecency_enabled: bool,
+    pub frecency_preselect: bool,
+    pub query_history_enabled: bool,
+    pub query_history_size: usize,
+    pub dedup_results: bool,
+    pub show_plugin_status: bool,
+    pub fallbacks: Vec<FallbackTemplate>,
+    pub theme: crate::search_instance::Theme,
+    pub fonts: crate::search_instance::FontSettings,
+    pub keymap: crate::search_instance::Keymap,
     pub log_level: quick_search_lib::LogLevelOrCustom,
     pub max_log_size: usize,
 }
@@ -159,12 +192,24 @@ impl Config {
             enabled: true,
             priority: 0,
             delay: 100,
+            auto_delay: false,
+            cache_enabled: true,
+            cache_ttl: 60,
+            collapsed: false,
             plugin_config: default_config,
         })
     }
     pub fn get_plugin(&self, name: &str) -> Option<&PluginConfig> {
         self.plugin_states.get(name)
     }
+    // the time in ms a plugin waits after the search delay before it is queried, auto delays are worked out from the given metrics
+    pub fn plugin_delay(&self, name: &str, metrics: &crate::search_instance::MetricsStore) -> u32 {
+        match self.get_plugin(name) {
+            Some(plugin) if plugin.auto_delay => metrics.auto_delay(name, self.auto_delay_min, self.auto_delay_max).unwrap_or(plugin.delay),
+            Some(plugin) => plugin.delay,
+            None => 100,
+        }
+    }
     pub fn save(&mut self) {
         // verify the format string is valid
         for item in chrono::format::StrftimeItems::new(&self.chrono_format_string) {
@@ -198,11 +243,120 @@ impl Config {
     }
 }
 
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplayMode {
+    // results grouped under their plugin, ordered by plugin priority
+    Grouped,
+    // every result in one list, ordered by how well it matches the query
+    Ranked,
+}
+
+impl std::fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DisplayMode::Grouped => write!(f, "Grouped"),
+            DisplayMode::Ranked => write!(f, "Ranked"),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreviewPlacement {
+    // only show the full result window when the selected result is truncated
+    Off,
+    Side,
+    // on the other side of the bar from the results
+    #[serde(alias = "Top")]
+    Opposite,
+}
+
+impl std::fmt::Display for PreviewPlacement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PreviewPlacement::Off => write!(f, "Off"),
+            PreviewPlacement::Side => write!(f, "Side"),
+            PreviewPlacement::Opposite => write!(f, "Opposite the results"),
+        }
+    }
+}
+
+// where the search bar sits on the screen
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BarAnchor {
+    Center,
+    // a third of the way down, leaving more room for results below
+    TopThird,
+    // the center moved by bar_offset_x and bar_offset_y
+    Custom,
+}
+
+impl std::fmt::Display for BarAnchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BarAnchor::Center => write!(f, "Center"),
+            BarAnchor::TopThird => write!(f, "Top third"),
+            BarAnchor::Custom => write!(f, "Custom offset"),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResultsPosition {
+    Below,
+    Above,
+}
+
+impl std::fmt::Display for ResultsPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResultsPosition::Below => write!(f, "Below the bar"),
+            ResultsPosition::Above => write!(f, "Above the bar"),
+        }
+    }
+}
+
+// which monitor the overlay opens on
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MonitorPlacement {
+    Primary,
+    // the monitor under the mouse cursor
+    Mouse,
+    // the monitor of the window that had focus when the hotkey was pressed
+    FocusedWindow,
+    // the monitor named in monitor_name
+    Named,
+}
+
+impl std::fmt::Display for MonitorPlacement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MonitorPlacement::Primary => write!(f, "Primary"),
+            MonitorPlacement::Mouse => write!(f, "Mouse"),
+            MonitorPlacement::FocusedWindow => write!(f, "Focused window"),
+            MonitorPlacement::Named => write!(f, "Named"),
+        }
+    }
+}
+
+// offered when no plugin returns anything, {query} is replaced with the query in the name and url
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct FallbackTemplate {
+    pub name: String,
+    pub url: String,
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
 pub struct PluginConfig {
     pub enabled: bool,
     pub priority: u32,
     pub delay: u32,
+    // pick the delay from the plugin's measured latency instead of using `delay`
+    pub auto_delay: bool,
+    pub cache_enabled: bool,
+    // seconds before a cached result is refreshed
+    pub cache_ttl: u32,
+    // the plugin's group only shows its header in the results window
+    pub collapsed: bool,
     pub plugin_config: quick_search_lib::Config,
 }
 
@@ -223,7 +377,19 @@ struct PossibleConfig {
     #[serde(default)]
     group_entries_while_unselected: Option<usize>,
     #[serde(default)]
+    results_per_plugin: Option<usize>,
+    #[serde(default)]
     total_search_delay: Option<usize>,
+    #[serde(default)]
+    auto_delay_min: Option<u32>,
+    #[serde(default)]
+    auto_delay_max: Option<u32>,
+    #[serde(default)]
+    search_timeout: Option<usize>,
+    #[serde(default)]
+    worker_threads: Option<usize>,
+    #[serde(default)]
+    cache_size: Option<usize>,
     // #[serde(default)]
     // show_countdown: Option<bool>,
     // #[serde(default)]
@@ -231,6 +397,18 @@ struct PossibleConfig {
     #[serde(default)]
     gap_between_search_bar_and_results: Option<f32>,
     #[serde(default)]
+    bar_anchor: Option<BarAnchor>,
+    #[serde(default)]
+    bar_offset_x: Option<f32>,
+    #[serde(default)]
+    bar_offset_y: Option<f32>,
+    #[serde(default)]
+    bar_width: Option<f32>,
+    #[serde(default)]
+    results_max_height: Option<f32>,
+    #[serde(default)]
+    results_position: Option<ResultsPosition>,
+    #[serde(default)]
     timezone: Option<chrono_tz::Tz>,
     #[serde(default)]
     chrono_format_string: Option<String>,
@@ -239,6 +417,38 @@ struct PossibleConfig {
     #[serde(default)]
     clock_enabled: Option<bool>,
     #[serde(default)]
+    show_icons: Option<bool>,
+    #[serde(default)]
+    display_mode: Option<DisplayMode>,
+    #[serde(default)]
+    preview_placement: Option<PreviewPlacement>,
+    #[serde(default)]
+    monitor_placement: Option<MonitorPlacement>,
+    #[serde(default)]
+    monitor_name: Option<String>,
+    #[serde(default)]
+    preview_delay: Option<usize>,
+    #[serde(default)]
+    frecency_enabled: Option<bool>,
+    #[serde(default)]
+    frecency_preselect: Option<bool>,
+    #[serde(default)]
+    query_history_enabled: Option<bool>,
+    #[serde(default)]
+    query_history_size: Option<usize>,
+    #[serde(default)]
+    dedup_results: Option<bool>,
+    #[serde(default)]
+    show_plugin_status: Option<bool>,
+    #[serde(default)]
+    fallbacks: Option<Vec<FallbackTemplate>>,
+    #[serde(default)]
+    theme: Option<crate::search_instance::Theme>,
+    #[serde(default)]
+    fonts: Option<crate::search_instance::FontSettings>,
+    #[serde(default)]
+    keymap: Option<crate::search_instance::Keymap>,
+    #[serde(default)]
     log_level: Option<quick_search_lib::LogLevelOrCustom>,
     #[serde(default)]
     max_log_size: Option<usize>,
@@ -254,14 +464,47 @@ impl From<PossibleConfig> for Config {
             appearance_delay: config.appearance_delay.unwrap_or(250),
             entries_around_cursor: config.entries_around_cursor.unwrap_or(2),
             group_entries_while_unselected: config.group_entries_while_unselected.unwrap_or(3),
+            results_per_plugin: config.results_per_plugin.unwrap_or(500),
             total_search_delay: config.total_search_delay.unwrap_or(500),
+            auto_delay_min: config.auto_delay_min.unwrap_or(0),
+            auto_delay_max: config.auto_delay_max.unwrap_or(1000),
+            search_timeout: config.search_timeout.unwrap_or(5000),
+            worker_threads: config.worker_threads.unwrap_or(crate::search_instance::DEFAULT_WORKER_THREADS),
+            cache_size: config.cache_size.unwrap_or(256),
             // show_countdown: config.show_countdown.unwrap_or(false),
             // flash_taskbar: config.flash_taskbar.unwrap_or(true),
             gap_between_search_bar_and_results: config.gap_between_search_bar_and_results.unwrap_or(10.0),
+            bar_anchor: config.bar_anchor.unwrap_or(BarAnchor::Center),
+            bar_offset_x: config.bar_offset_x.unwrap_or(0.0),
+            bar_offset_y: config.bar_offset_y.unwrap_or(0.0),
+            bar_width: config.bar_width.unwrap_or(0.0),
+            results_max_height: config.results_max_height.unwrap_or(0.0),
+            results_position: config.results_position.unwrap_or(ResultsPosition::Below),
             timezone: config.timezone.unwrap_or(chrono_tz::Tz::UTC),
             chrono_format_string: config.chrono_format_string.unwrap_or_else(|| "%Y-%m-%d %H:%M:%S".to_string()),
             time_font_size: config.time_font_size.unwrap_or(20.0),
             clock_enabled: config.clock_enabled.unwrap_or(true),
+            show_icons: config.show_icons.unwrap_or(true),
+            display_mode: config.display_mode.unwrap_or(DisplayMode::Grouped),
+            preview_placement: config.preview_placement.unwrap_or(PreviewPlacement::Off),
+            monitor_placement: config.monitor_placement.unwrap_or(MonitorPlacement::Primary),
+            monitor_name: config.monitor_name.unwrap_or_default(),
+            preview_delay: config.preview_delay.unwrap_or(150),
+            frecency_enabled: config.frecency_enabled.unwrap_or(true),
+            frecency_preselect: config.frecency_preselect.unwrap_or(false),
+            query_history_enabled: config.query_history_enabled.unwrap_or(true),
+            query_history_size: config.query_history_size.unwrap_or(100),
+            dedup_results: config.dedup_results.unwrap_or(false),
+            show_plugin_status: config.show_plugin_status.unwrap_or(true),
+            fallbacks: config.fallbacks.unwrap_or_else(|| {
+                vec![FallbackTemplate {
+                    name: "Search the web for {query}".to_string(),
+                    url: "https://duckduckgo.com/?q={query}".to_string(),
+                }]
+            }),
+            theme: config.theme.unwrap_or_default(),
+            fonts: config.fonts.unwrap_or_default(),
+            keymap: config.keymap.map(crate::search_instance::Keymap::filled).unwrap_or_default(),
             log_level: config.log_level.unwrap_or(quick_search_lib::LogLevelOrCustom::from_min_level(quick_search_lib::LogLevel::Error)),
             max_log_size: config.max_log_size.unwrap_or(1024),
         }
@@ -277,6 +520,14 @@ struct PossiblePluginConfig {
     #[serde(default)]
     delay: Option<u32>,
     #[serde(default)]
+    auto_delay: Option<bool>,
+    #[serde(default)]
+    cache_enabled: Option<bool>,
+    #[serde(default)]
+    cache_ttl: Option<u32>,
+    #[serde(default)]
+    collapsed: Option<bool>,
+    #[serde(default)]
     plugin_config: Option<quick_search_lib::Config>,
 }
 
@@ -286,6 +537,10 @@ impl From<PossiblePluginConfig> for PluginConfig {
             enabled: config.enabled.unwrap_or(true),
             priority: config.priority.unwrap_or(0),
             delay: config.delay.unwrap_or(250),
+            auto_delay: config.auto_delay.unwrap_or(false),
+            cache_enabled: config.cache_enabled.unwrap_or(true),
+            cache_ttl: config.cache_ttl.unwrap_or(60),
+            collapsed: config.collapsed.unwrap_or(false),
             plugin_config: config.plugin_config.unwrap_or_default(),
         }
     }
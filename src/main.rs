@@ -42,6 +42,13 @@ lazy_static::lazy_static! {
     };
     static ref CURRENT_PATH: std::path::PathBuf = std::env::current_exe().expect("Failed to get current exe path");
     static ref CORRECT_PATH: std::path::PathBuf = get_correct_path();
+    static ref PLUGIN_METRICS: search_instance::MetricsStore = search_instance::MetricsStore::default();
+    static ref SEARCH_POOL: search_instance::WorkerPool = search_instance::WorkerPool::default();
+    // previews get their own worker so they never wait behind a slow search
+    static ref PREVIEW_POOL: search_instance::WorkerPool = search_instance::WorkerPool::new(1);
+    static ref RESULT_CACHE: search_instance::ResultCache = search_instance::ResultCache::default();
+    static ref USAGE_STORE: search_instance::UsageStore = search_instance::UsageStore::load();
+    static ref QUERY_HISTORY: search_instance::QueryHistory = search_instance::QueryHistory::load();
 }
 
 fn to_pixel_image(bytes: &[u8]) -> PixelImage {
@@ -70,6 +77,8 @@ fn main() {
     {
         let cfg = (*CONFIG_FILE).lock();
         (*LOGGER).set_log_level(cfg.get().log_level);
+        // start the search workers now so the first search doesn't wait for them
+        SEARCH_POOL.resize(cfg.get().worker_threads);
     }
 
     LOGGER.trace("Logging initialized");
@@ -0,0 +1,103 @@
+use quick_search_lib::SearchResult;
+
+use crate::LOGGER;
+use quick_search_lib::Log;
+
+use super::Plugin;
+
+// the plugin abi only exposes a single execute and has no way to declare actions, so every other action is provided by the host
+// and works off the title and context of the result, until quick-search-lib can list a plugin's own actions next to these
+pub const EXECUTE: &str = "execute";
+pub const COPY_TITLE: &str = "copy_title";
+pub const COPY_CONTEXT: &str = "copy_context";
+pub const COPY_MARKDOWN: &str = "copy_markdown";
+pub const OPEN_FOLDER: &str = "open_folder";
+// followed by the name of the plugin whose copy of a merged duplicate should be executed
+pub const EXECUTE_WITH: &str = "execute_with:";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultAction {
+    pub id: String,
+    pub name: String,
+    pub shortcut: Option<egui::Key>,
+}
+
+impl ResultAction {
+    fn new(id: &str, name: &str, shortcut: Option<egui::Key>) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            shortcut,
+        }
+    }
+
+    pub fn execute_with(plugin_name: &str) -> Self {
+        Self::new(&format!("{}{}", EXECUTE_WITH, plugin_name), &format!("Open with {}", plugin_name), None)
+    }
+}
+
+impl Plugin {
+    // only the host's actions for now, plugin declared ones would go before them once the abi has them
+    pub fn actions(&self, result: &SearchResult) -> Vec<ResultAction> {
+        host_actions(result)
+    }
+
+    pub fn run_action(&self, id: &str, result: &SearchResult, egui_context: &egui::Context) {
+        LOGGER.trace(&format!("running action {} for {}", id, self.name));
+        match id {
+            EXECUTE => self.execute(result),
+            COPY_TITLE => egui_context.output_mut(|o| o.copied_text = result.title().to_string()),
+            COPY_CONTEXT => egui_context.output_mut(|o| o.copied_text = result.context().to_string()),
+            COPY_MARKDOWN => egui_context.output_mut(|o| o.copied_text = format!("[{}]({})", result.title(), result.context())),
+            OPEN_FOLDER => match result_path(result) {
+                Some(path) => {
+                    let mut command = std::process::Command::new("explorer");
+                    command.arg("/select,");
+                    // explorer splits on commas, so the path is always quoted instead of only when it has spaces in it
+                    #[cfg(windows)]
+                    std::os::windows::process::CommandExt::raw_arg(&mut command, format!("\"{}\"", path.display()));
+                    #[cfg(not(windows))]
+                    command.arg(&path);
+                    if let Err(e) = command.spawn() {
+                        LOGGER.error(&format!("Failed to open containing folder: {}", e));
+                    }
+                }
+                None => LOGGER.warn("result does not point to a file"),
+            },
+            _ => LOGGER.error(&format!("unknown action {}", id)),
+        }
+    }
+}
+
+// what the host can do with any result, the same for every plugin
+fn host_actions(result: &SearchResult) -> Vec<ResultAction> {
+    let mut actions = vec![
+        ResultAction::new(EXECUTE, "Open", Some(egui::Key::Enter)),
+        ResultAction::new(COPY_TITLE, "Copy title", Some(egui::Key::C)),
+    ];
+    if !result.context().is_empty() {
+        actions.push(ResultAction::new(COPY_CONTEXT, "Copy context", Some(egui::Key::X)));
+        actions.push(ResultAction::new(COPY_MARKDOWN, "Copy as markdown", Some(egui::Key::M)));
+    }
+    if result_path(result).is_some() {
+        actions.push(ResultAction::new(OPEN_FOLDER, "Open containing folder", Some(egui::Key::F)));
+    }
+    actions
+}
+
+// what middle-clicking a result does: files are shown in their folder, anything else has its title copied
+pub fn secondary(result: &SearchResult) -> &'static str {
+    if result_path(result).is_some() {
+        OPEN_FOLDER
+    } else {
+        COPY_TITLE
+    }
+}
+
+// results that point at something on disk usually carry the path in their context, falling back to the title
+fn result_path(result: &SearchResult) -> Option<std::path::PathBuf> {
+    [result.context(), result.title()]
+        .into_iter()
+        .map(std::path::PathBuf::from)
+        .find(|path| path.is_absolute() && path.exists())
+}
@@ -0,0 +1,62 @@
+use quick_search_lib::SearchResult;
+
+use crate::config::FallbackTemplate;
+use crate::search_instance::SearchMetadata;
+use crate::LOGGER;
+use quick_search_lib::Log;
+
+// fallbacks only come from the config, the plugin abi has no way for a plugin to register one
+// they are shown as a group of their own, under an id no plugin file can have
+const FALLBACK_ID: &str = "<fallbacks>";
+
+pub fn plugin_id() -> quick_search_lib::PluginId {
+    quick_search_lib::PluginId { filename: FALLBACK_ID.into() }
+}
+
+pub fn metadata() -> SearchMetadata {
+    SearchMetadata {
+        pretty_name: egui::text::LayoutJob::single_section("Fallbacks".to_string(), egui::TextFormat::default()),
+        priority: 0,
+        raw_name: FALLBACK_ID.to_string(),
+        id: plugin_id(),
+        icon: None,
+    }
+}
+
+// one result per template, the title says what it does and the context holds the url it opens
+pub fn results(templates: &[FallbackTemplate], query: &str) -> Vec<SearchResult> {
+    templates
+        .iter()
+        .filter(|t| !t.url.is_empty())
+        .map(|t| SearchResult::new(&t.name.replace("{query}", query)).set_context(&t.url.replace("{query}", &urlencoding::encode(query))))
+        .collect()
+}
+
+pub fn execute(result: &SearchResult) {
+    let url = result.context();
+    // only web urls are opened, a template pointing at a file or a program isn't run
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        LOGGER.error(&format!("Refusing to open fallback {}, only http and https urls are supported", url));
+        return;
+    }
+    LOGGER.trace(&format!("opening fallback {}", url));
+    if let Err(e) = webbrowser::open(url) {
+        LOGGER.error(&format!("Failed to open fallback: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_query_is_percent_encoded() {
+        let templates = vec![FallbackTemplate {
+            name: "Search the web for {query}".to_string(),
+            url: "https://example.com/?q={query}".to_string(),
+        }];
+        let results = results(&templates, "a b&c=d/é");
+        assert_eq!(results[0].title(), "Search the web for a b&c=d/é");
+        assert_eq!(results[0].context(), "https://example.com/?q=a%20b%26c%3Dd%2F%C3%A9");
+    }
+}
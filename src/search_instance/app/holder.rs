@@ -1,39 +1,547 @@
-use std::rc::Rc;
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use quick_search_lib::{PluginId, SearchResult};
 
-use crate::LOGGER;
-use quick_search_lib::Log;
-
 use crate::search_instance::SearchMetadata;
 
+use super::ranking::match_quality;
+
+// how far page up/down moves the cursor in ranked mode, where there are no groups to jump between
+const RANKED_PAGE: usize = 5;
+// how much each point of plugin priority adds to a result's match quality (0 to 1) in ranked mode, the highest priority is worth about a quarter
+const PRIORITY_WEIGHT: f32 = 0.002;
+
 #[derive(Default)]
 pub struct ResultHolder {
     results: Vec<ResultGroup>,
+    // the global index of every group's first result, and the number of results, kept in step with `results`
+    starts: Vec<usize>,
+    total: usize,
+    // how many results a group shows before the rest wait behind a "show more" row, 0 shows everything
+    result_cap: usize,
     cursor: usize,
+    // the result under the cursor as (plugin, index within its group), the cursor follows it when groups arrive, get re-sorted or go away
+    selected: Option<(String, usize)>,
+    // while the user is moving through the results, late groups are never placed above the selected one
+    hold_layout: bool,
+    // merge results that several plugins returned into the copy from the plugin with the highest priority
+    dedup: bool,
+    // results marked for batch execution, by plugin and content so they stay marked while the layout changes
+    marked: Vec<(String, SearchResult)>,
+    // plugins whose groups only show their header, the cursor skips over them
+    collapsed: HashSet<String>,
+    // only the group under the cursor is shown, with all of its results
+    focus: bool,
+    // when set, results are shown as a single list ordered by score instead of grouped by plugin
+    ranking: Option<Ranking>,
+}
+
+struct Ranking {
+    query: String,
+    // (group, index within group) for every result, best first
+    order: Vec<(usize, usize)>,
 }
 
 impl ResultHolder {
     pub fn clear(&mut self) {
         self.results.clear();
+        self.reindex();
         self.cursor = 0;
+        self.selected = None;
+        self.marked.clear();
+        if let Some(ranking) = &mut self.ranking {
+            ranking.order.clear();
+        }
+    }
+
+    pub fn toggle_mark(&mut self, index: usize) {
+        let (g, i) = match self.position(index) {
+            Some(position) => position,
+            None => return,
+        };
+        let name = self.results[g].metadata.raw_name.clone();
+        let result = &self.results[g].results[i].result;
+        match self.marked.iter().position(|(n, r)| *n == name && same_result(r, result)) {
+            Some(m) => {
+                self.marked.remove(m);
+            }
+            None => self.marked.push((name, result.clone())),
+        }
+    }
+
+    pub fn toggle_mark_at_cursor(&mut self) {
+        self.toggle_mark(self.cursor);
+    }
+
+    fn is_marked(&self, name: &str, result: &SearchResult) -> bool {
+        self.marked.iter().any(|(n, r)| n == name && same_result(r, result))
+    }
+
+    // every marked result that is still there, in the order they are shown
+    pub fn marked(&self) -> Vec<(SearchResult, PluginId)> {
+        if self.marked.is_empty() {
+            return Vec::new();
+        }
+        (0..self.len())
+            .filter_map(|index| self.position(index))
+            .filter_map(|(g, i)| {
+                let group = &self.results[g];
+                let result = &group.results[i].result;
+                self.is_marked(&group.metadata.raw_name, result).then(|| (result.clone(), group.metadata.id.clone()))
+            })
+            .collect()
+    }
+
+    pub fn set_ranking(&mut self, ranked: bool, query: &str) {
+        match (&self.ranking, ranked) {
+            (Some(ranking), true) if ranking.query == query => {}
+            (_, true) => {
+                // scores from another query don't apply any more
+                for group in &mut self.results {
+                    group.results.iter_mut().for_each(|entry| entry.score = None);
+                }
+                self.ranking = Some(Ranking {
+                    query: query.to_string(),
+                    order: Vec::new(),
+                });
+                self.rerank();
+                self.follow_selected();
+            }
+            (Some(_), false) => {
+                self.ranking = None;
+                self.follow_selected();
+            }
+            (None, false) => {}
+        }
+    }
+
+    pub fn hold_layout(&mut self, hold: bool) {
+        self.hold_layout = hold;
+    }
+
+    pub fn set_dedup(&mut self, dedup: bool) {
+        self.dedup = dedup;
+    }
+
+    pub fn set_result_cap(&mut self, cap: usize) {
+        self.result_cap = cap;
     }
+
+    // loads the next batch of results waiting behind a group's "show more" row
+    pub fn show_more(&mut self, name: &str) {
+        let g = match self.results.iter().position(|g| g.metadata.raw_name == name) {
+            Some(g) => g,
+            None => return,
+        };
+        let count = if self.result_cap == 0 { usize::MAX } else { self.result_cap }.min(self.results[g].more.len());
+        let batch = self.results[g].more.drain(..count).collect::<Vec<_>>();
+        let metadata = self.results[g].metadata.clone();
+        let batch = if self.dedup { self.merge_duplicates(batch, &metadata) } else { batch };
+        if let Some(group) = self.results.iter_mut().find(|g| g.metadata.raw_name == name) {
+            group.results.extend(batch);
+        }
+        self.reindex();
+        self.rerank();
+        self.follow_selected();
+    }
+
+    // the group under the cursor, for showing more of it from the keyboard
+    pub fn group_at_cursor(&self) -> Option<String> {
+        self.position(self.cursor).map(|(g, _)| self.results[g].metadata.raw_name.clone())
+    }
+
+    pub fn set_collapsed(&mut self, names: impl IntoIterator<Item = String>) {
+        self.collapsed = names.into_iter().collect();
+    }
+
+    // returns whether the group is collapsed now
+    pub fn toggle_collapsed(&mut self, name: &str) -> bool {
+        let collapsed = if self.collapsed.remove(name) {
+            false
+        } else {
+            self.collapsed.insert(name.to_string());
+            true
+        };
+        self.skip_hidden(true);
+        collapsed
+    }
+
+    // collapses the group under the cursor, returning its name
+    pub fn collapse_at_cursor(&mut self) -> Option<String> {
+        if self.ranking.is_some() {
+            return None;
+        }
+        let (g, _) = self.position(self.cursor)?;
+        let name = self.results[g].metadata.raw_name.clone();
+        self.collapsed.insert(name.clone());
+        self.skip_hidden(true);
+        Some(name)
+    }
+
+    // expands the first collapsed group after the cursor, wrapping around, and moves the cursor into it
+    pub fn expand_next_collapsed(&mut self) -> Option<String> {
+        if self.ranking.is_some() {
+            return None;
+        }
+        let current = self.position(self.cursor).map(|(g, _)| g).unwrap_or(0);
+        let count = self.results.len();
+        let g = (1..=count)
+            .map(|offset| (current + offset) % count)
+            .find(|g| !self.results[*g].results.is_empty() && self.collapsed.contains(&self.results[*g].metadata.raw_name))?;
+        let name = self.results[g].metadata.raw_name.clone();
+        self.collapsed.remove(&name);
+        self.cursor = self.group_start(g);
+        self.sync_selected();
+        Some(name)
+    }
+
+    pub fn focused(&self) -> bool {
+        self.focus
+    }
+
+    pub fn toggle_focus(&mut self) {
+        // ranked mode has no groups to focus on
+        self.focus = !self.focus && self.ranking.is_none();
+    }
+
+    fn group_start(&self, g: usize) -> usize {
+        self.starts[g]
+    }
+
+    fn group_range(&self, g: usize) -> std::ops::Range<usize> {
+        self.starts[g]..self.starts[g] + self.results[g].results.len()
+    }
+
+    // recomputes where every group starts, after anything that adds, removes or moves results between groups
+    fn reindex(&mut self) {
+        self.starts.clear();
+        let mut start = 0;
+        for group in self.results.iter() {
+            self.starts.push(start);
+            start += group.results.len();
+        }
+        self.total = start;
+    }
+
+    fn hidden(&self, index: usize) -> bool {
+        if self.ranking.is_some() {
+            return false;
+        }
+        match self.position(index) {
+            Some((g, _)) => self.collapsed.contains(&self.results[g].metadata.raw_name),
+            None => false,
+        }
+    }
+
+    // moves the cursor off collapsed groups in the given direction, wrapping around, and leaves it alone if everything is collapsed
+    fn skip_hidden(&mut self, forward: bool) {
+        let len = self.len();
+        for _ in 0..len {
+            if !self.hidden(self.cursor) {
+                break;
+            }
+            self.cursor = if forward { (self.cursor + 1) % len } else { self.cursor.checked_sub(1).unwrap_or(len - 1) };
+        }
+        self.sync_selected();
+    }
+
+    // orders every result by how well it matches, call follow_selected afterwards to put the cursor back on its result
+    // plugin priority adds a small weight, enough to lift a plugin over one with a similar match but not over a clearly better one,
+    // and results that don't match at all sink below the ones that do instead of being dropped, since a plugin can match on things it doesn't show
+    // the abi gives plugins no way to score their own results, so the score is always the host's
+    // scores are kept on the results until the query changes, so a plugin answering only scores its own results
+    fn rerank(&mut self) {
+        let query = match &self.ranking {
+            Some(ranking) => ranking.query.clone(),
+            None => return,
+        };
+        let mut scored = Vec::with_capacity(self.len());
+        for (g, group) in self.results.iter_mut().enumerate() {
+            let weight = group.metadata.priority as f32 * PRIORITY_WEIGHT;
+            for (i, entry) in group.results.iter_mut().enumerate() {
+                let score = *entry.score.get_or_insert_with(|| {
+                    let title = match_quality(&query, entry.result.title());
+                    let context = match_quality(&query, entry.result.context()).map(|q| q * 0.5);
+                    match (title, context) {
+                        (None, None) => -1.0,
+                        (title, context) => title.unwrap_or(0.0).max(context.unwrap_or(0.0)),
+                    }
+                });
+                scored.push((score + weight, g, i));
+            }
+        }
+        // a stable sort, so equal scores keep group order, which is by priority already
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        if let Some(ranking) = &mut self.ranking {
+            ranking.order = scored.into_iter().map(|(_, g, i)| (g, i)).collect();
+        }
+    }
+
+    fn sync_selected(&mut self) {
+        self.selected = self.position(self.cursor).map(|(g, i)| (self.results[g].metadata.raw_name.clone(), i));
+    }
+
+    // moves the cursor back onto the selected result, after anything that changes the layout
+    fn follow_selected(&mut self) {
+        if let Some((name, index)) = &self.selected {
+            if let Some(cursor) = self.index_of(name, *index) {
+                self.cursor = cursor;
+                return;
+            }
+        }
+        // the selected result is gone, stay where we were and select whatever is there now
+        self.cursor = self.cursor.min(self.len().saturating_sub(1));
+        self.skip_hidden(true);
+    }
+
+    fn index_of(&self, name: &str, index: usize) -> Option<usize> {
+        let g = self.results.iter().position(|g| g.metadata.raw_name == name)?;
+        if index >= self.results[g].results.len() {
+            return None;
+        }
+        match &self.ranking {
+            Some(ranking) => ranking.order.iter().position(|p| *p == (g, index)),
+            None => Some(self.starts[g] + index),
+        }
+    }
+
+    fn position(&self, index: usize) -> Option<(usize, usize)> {
+        if let Some(ranking) = &self.ranking {
+            return ranking.order.get(index).copied();
+        }
+        if index >= self.total {
+            return None;
+        }
+        // the last group starting at or before the index, empty groups share their start with the next one so they are skipped
+        let g = self.starts.partition_point(|start| *start <= index) - 1;
+        Some((g, index - self.starts[g]))
+    }
+
     pub fn add_results(&mut self, results: Vec<SearchResult>, metadata: SearchMetadata) {
-        let res_len = results.len();
+        let metadata = match self.results.iter().find(|g| g.metadata.raw_name == metadata.raw_name) {
+            Some(group) => group.metadata.clone(),
+            None => Rc::new(metadata),
+        };
+        let entries = results.into_iter().map(ResultEntry::new).collect();
+        self.add_entries(entries, metadata);
+    }
+
+    fn add_entries(&mut self, entries: Vec<ResultEntry>, metadata: Rc<SearchMetadata>) {
         let this_name = metadata.raw_name.clone();
-        self.results.push(ResultGroup { results, metadata: Rc::new(metadata) });
-        self.results.sort_by(|a, b| b.metadata.priority.cmp(&a.metadata.priority));
-        // if cursor was located after where the new result was added, add the length of the new results to the cursor
-        let mut len_before = 0;
-        for results in &self.results {
-            if results.metadata.raw_name == this_name {
-                break;
+        let entries = if self.dedup { self.merge_duplicates(entries, &metadata) } else { entries };
+        if entries.is_empty() {
+            self.rerank();
+            self.follow_selected();
+            return;
+        }
+        // a plugin only ever has one group, anything more for it goes on the end
+        let cap = self.result_cap;
+        match self.results.iter_mut().find(|g| g.metadata.raw_name == this_name) {
+            Some(group) => group.push_capped(entries, cap),
+            None => {
+                // after every group with at least the same priority, like a stable sort would put it
+                let mut insert_at = self.results.iter().position(|g| g.metadata.priority < metadata.priority).unwrap_or(self.results.len());
+                if self.hold_layout && self.ranking.is_none() {
+                    if let Some(selected_group) = self.selected.as_ref().and_then(|(name, _)| self.results.iter().position(|g| g.metadata.raw_name == *name)) {
+                        // a late group never pushes down what the user is looking at
+                        insert_at = insert_at.max(selected_group + 1);
+                    }
+                }
+                let mut group = ResultGroup {
+                    results: Vec::new(),
+                    more: Vec::new(),
+                    metadata,
+                    finished: false,
+                };
+                group.push_capped(entries, cap);
+                self.results.insert(insert_at, group);
             }
-            len_before += results.results.len();
         }
-        if self.cursor >= len_before {
-            self.cursor += res_len;
+        self.reindex();
+        self.rerank();
+        self.follow_selected();
+    }
+
+    // folds incoming results into copies other plugins already returned, the copy from the plugin with the highest priority is the one shown
+    fn merge_duplicates(&mut self, entries: Vec<ResultEntry>, metadata: &Rc<SearchMetadata>) -> Vec<ResultEntry> {
+        let mut keys = self.dedup_keys(&metadata.raw_name);
+        let mut kept = Vec::with_capacity(entries.len());
+        for mut entry in entries {
+            let (g, i) = match keys.get(&dedup_key(&entry.result)) {
+                Some(position) => *position,
+                None => {
+                    kept.push(entry);
+                    continue;
+                }
+            };
+            if self.results[g].metadata.priority >= metadata.priority {
+                self.results[g].results[i].also_from.push(Duplicate {
+                    metadata: metadata.clone(),
+                    result: entry.result,
+                });
+                self.results[g].results[i].also_from.extend(entry.also_from);
+                continue;
+            }
+            // the incoming copy wins, the old one is taken out of its group and remembered as a duplicate
+            let other = self.results[g].metadata.clone();
+            let selected_here = self.selected.as_ref().map(|(name, index)| *name == other.raw_name && *index == i).unwrap_or(false);
+            let old = self.take_entry(g, i);
+            entry.also_from.push(Duplicate { metadata: other, result: old.result });
+            entry.also_from.extend(old.also_from);
+            if selected_here {
+                let index = self.results.iter().find(|g| g.metadata.raw_name == metadata.raw_name).map(|g| g.results.len()).unwrap_or(0) + kept.len();
+                self.selected = Some((metadata.raw_name.clone(), index));
+            }
+            kept.push(entry);
+            keys = self.dedup_keys(&metadata.raw_name);
         }
+        kept
+    }
+
+    fn dedup_keys(&self, except: &str) -> HashMap<(String, String), (usize, usize)> {
+        let mut keys = HashMap::new();
+        for (g, group) in self.results.iter().enumerate() {
+            if group.metadata.raw_name == except {
+                continue;
+            }
+            for (i, entry) in group.results.iter().enumerate() {
+                keys.entry(dedup_key(&entry.result)).or_insert((g, i));
+            }
+        }
+        keys
+    }
+
+    // removes a single result, dropping its group if it was the last one, and keeps the selection on the same result
+    fn take_entry(&mut self, g: usize, i: usize) -> ResultEntry {
+        let name = self.results[g].metadata.raw_name.clone();
+        let entry = self.results[g].results.remove(i);
+        if let Some((selected_name, index)) = &mut self.selected {
+            if *selected_name == name && *index > i {
+                *index -= 1;
+            }
+        }
+        if self.results[g].results.is_empty() && self.results[g].more.is_empty() {
+            self.results.remove(g);
+        }
+        self.reindex();
+        entry
+    }
+
+    pub fn remove_group(&mut self, name: &str) {
+        let mut start = 0;
+        for (g, group) in self.results.iter().enumerate() {
+            if group.metadata.raw_name == name {
+                if self.selected.as_ref().map(|(n, _)| n == name).unwrap_or(false) {
+                    // the cursor was inside the removed group, park it where the group used to start
+                    self.selected = None;
+                    if self.ranking.is_none() {
+                        self.cursor = start;
+                    }
+                }
+                let removed = self.results.remove(g);
+                self.reindex();
+                for group in self.results.iter_mut() {
+                    for entry in group.results.iter_mut() {
+                        entry.also_from.retain(|d| d.metadata.raw_name != name);
+                    }
+                }
+                // copies other plugins returned were only hidden behind this group, give them back
+                for duplicate in removed.results.into_iter().flat_map(|e| e.also_from) {
+                    self.add_entries(vec![ResultEntry::new(duplicate.result)], duplicate.metadata);
+                }
+                self.rerank();
+                self.follow_selected();
+                return;
+            }
+            start += group.results.len();
+        }
+    }
+
+    // swaps a group's results for a fresh set, used when a cached group gets refreshed
+    pub fn replace_results(&mut self, results: Vec<SearchResult>, metadata: SearchMetadata) {
+        let name = metadata.raw_name.clone();
+        // if the selected result is still there after the refresh, keep it selected
+        let selected = match &self.selected {
+            Some((n, index)) if *n == name => self.results.iter().find(|g| g.metadata.raw_name == name).and_then(|g| g.results.get(*index)).map(|e| e.result.clone()),
+            _ => None,
+        };
+        let cursor = self.cursor;
+        self.remove_group(&name);
+        self.add_results(results, metadata);
+        if let Some(old) = selected {
+            let index = self
+                .results
+                .iter()
+                .find(|g| g.metadata.raw_name == name)
+                .and_then(|g| g.results.iter().position(|e| e.result.title() == old.title() && e.result.context() == old.context()));
+            match index {
+                Some(index) => self.selected = Some((name, index)),
+                None => {
+                    self.selected = None;
+                    self.cursor = cursor;
+                }
+            }
+            self.follow_selected();
+        }
+    }
+
+    // stable sorts a group by the given score, highest first, keeping the cursor on the result it was on
+    pub fn reorder_group(&mut self, name: &str, score: impl Fn(&SearchResult) -> f32) {
+        let holds_selection = self.selected.as_ref().map(|(n, _)| n == name).unwrap_or(false);
+        if self.hold_layout && holds_selection {
+            // the user is looking at this group, leave it alone
+            return;
+        }
+        let group = match self.results.iter_mut().find(|g| g.metadata.raw_name == name) {
+            Some(group) => group,
+            None => return,
+        };
+        let mut order = group.results.iter().map(|e| score(&e.result)).enumerate().collect::<Vec<_>>();
+        if order.iter().all(|(_, s)| *s <= 0.0) {
+            return;
+        }
+        order.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let order = order.into_iter().map(|(i, _)| i).collect::<Vec<_>>();
+        let mut old = std::mem::take(&mut group.results).into_iter().map(Some).collect::<Vec<_>>();
+        group.results = order.iter().filter_map(|i| old[*i].take()).collect();
+
+        if holds_selection {
+            if let Some((_, index)) = &mut self.selected {
+                *index = order.iter().position(|i| i == index).unwrap_or(*index);
+            }
+        }
+        self.rerank();
+        self.follow_selected();
+    }
+
+    // the global index of the highest scoring result, if any result scores above zero
+    pub fn best_result(&self, score: impl Fn(&str, &SearchResult) -> f32) -> Option<usize> {
+        let mut best: Option<(f32, usize)> = None;
+        for index in 0..self.len() {
+            let (g, i) = self.position(index)?;
+            let group = &self.results[g];
+            if self.collapsed.contains(&group.metadata.raw_name) && self.ranking.is_none() {
+                continue;
+            }
+            let s = score(&group.metadata.raw_name, &group.results[i].result);
+            if s > 0.0 && best.map(|(b, _)| s > b).unwrap_or(true) {
+                best = Some((s, index));
+            }
+        }
+        best.map(|(_, index)| index)
+    }
+
+    pub fn finish_group(&mut self, name: &str) {
+        if let Some(group) = self.results.iter_mut().find(|g| g.metadata.raw_name == name) {
+            group.finished = true;
+        }
+    }
+
+    pub fn has_group(&self, name: &str) -> bool {
+        self.results.iter().any(|g| g.metadata.raw_name == name && !g.results.is_empty())
     }
 
     pub fn is_empty(&self) -> bool {
@@ -41,62 +549,84 @@ impl ResultHolder {
     }
 
     pub fn increment_cursor(&mut self) {
+        if let Some(group) = self.focus_range() {
+            // wrap around inside the focused group
+            self.cursor = if self.cursor + 1 >= group.end { group.start } else { self.cursor + 1 };
+            self.sync_selected();
+            return;
+        }
         self.cursor += 1;
         if self.cursor >= self.len() {
             self.cursor = 0;
         }
+        self.skip_hidden(true);
     }
 
     pub fn decrement_cursor(&mut self) {
+        if let Some(group) = self.focus_range() {
+            self.cursor = if self.cursor <= group.start { group.end.saturating_sub(1) } else { self.cursor - 1 };
+            self.sync_selected();
+            return;
+        }
         if self.cursor == 0 {
             self.cursor = self.len().saturating_sub(1);
         } else {
             self.cursor -= 1;
         }
+        self.skip_hidden(false);
+    }
+
+    // the global indices of the group under the cursor, while focusing on it
+    fn focus_range(&self) -> Option<std::ops::Range<usize>> {
+        if !self.focus || self.ranking.is_some() {
+            return None;
+        }
+        let (g, _) = self.position(self.cursor)?;
+        let start = self.group_start(g);
+        Some(start..start + self.results[g].results.len())
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
     }
 
     pub fn clear_cursor(&mut self) {
         self.cursor = 0;
+        self.skip_hidden(true);
     }
 
     pub fn raw_set_cursor(&mut self, cursor: usize) {
         self.cursor = cursor;
+        self.sync_selected();
     }
 
     pub fn len(&self) -> usize {
-        self.results.iter().map(|g| g.results.len()).sum()
+        self.total
     }
 
     pub fn jump_backward(&mut self, selected: bool) {
         // we want to set self.cursor to the last result of the previous group
         // if there is no previous group, set self.cursor to the last result of the last group
 
-        if selected {
-            let mut cursor = None;
-
-            let mut done = false;
-
-            let mut end_of_final_group = 0;
-
-            for (i, (y, _)) in self.results.iter().flat_map(|g| g.results.iter().enumerate()).enumerate() {
-                // i is the index in GLOBAL SPACE
-                // the cursor is in GLOBAL SPACE
-                // we need to detect the bounds of the previous group, so cursor will be the last time we crossed a group boundary
-                // once we cross self.cursor for i, we can break
-
-                if y == 0 && !done {
-                    cursor = i.checked_sub(1);
-                }
-                if i == self.cursor {
-                    done = true;
-                }
-                end_of_final_group = i;
-            }
+        if self.ranking.is_some() {
+            self.cursor = if selected { self.cursor.saturating_sub(RANKED_PAGE) } else { self.len().saturating_sub(1) };
+            self.sync_selected();
+            return;
+        }
 
-            self.cursor = cursor.unwrap_or(end_of_final_group);
+        if selected {
+            // the start of the group the cursor is in, one before that is the end of the previous group
+            let g = self.starts.partition_point(|start| *start <= self.cursor).saturating_sub(1);
+            self.cursor = self.starts.get(g).and_then(|start| start.checked_sub(1)).unwrap_or(self.total.saturating_sub(1));
         } else {
             // not selected, jump to final result of last group
-            self.cursor = self.results.iter().flat_map(|g| g.results.iter()).count().saturating_sub(1);
+            self.cursor = self.total.saturating_sub(1);
+        }
+        self.skip_hidden(false);
+        if let Some(group) = self.focus_range() {
+            // a focused group is read from the top
+            self.cursor = group.start;
+            self.sync_selected();
         }
     }
 
@@ -104,145 +634,352 @@ impl ResultHolder {
         // we want to set self.cursor to the first result of the next group
         // if there is no next group, set self.cursor to the first result of the first group
 
-        if selected {
-            let mut cursor = None;
-            let mut break_next = false;
-
-            for (i, (y, _)) in self.results.iter().flat_map(|g| g.results.iter().enumerate()).enumerate() {
-                // i is the index in GLOBAL SPACE
-                // the cursor is in GLOBAL SPACE
-                // we need to detect the bounds of the next group, so cursor will be the first time we cross a group boundary
-                // once we cross self.cursor for i, we can break
-
-                if y == 0 && break_next {
-                    LOGGER.trace(&format!("breaking at {}", i));
-                    cursor = Some(i);
-                    break;
-                }
-                if i == self.cursor {
-                    break_next = true;
-                }
-            }
+        if self.ranking.is_some() {
+            self.cursor = if selected { (self.cursor + RANKED_PAGE).min(self.len().saturating_sub(1)) } else { 0 };
+            self.sync_selected();
+            return;
+        }
 
-            self.cursor = cursor.unwrap_or(0);
+        if selected {
+            // the first group starting after the cursor, empty groups share their start with the next one or the end
+            let next = self.starts.partition_point(|start| *start <= self.cursor);
+            self.cursor = self.starts.get(next).copied().filter(|start| *start < self.total).unwrap_or(0);
         } else {
             // not selected, jump to first result of first group
             self.cursor = 0;
         }
+        self.skip_hidden(true);
     }
 
-    pub fn get_from_cursor(&mut self) -> Option<(&SearchResult, PluginId)> {
+    pub fn get_from_cursor(&self) -> Option<(&SearchResult, PluginId)> {
         if self.results.is_empty() {
             return None;
         }
 
-        let mut cursor = self.cursor;
+        let (g, i) = self.position(self.cursor)?;
+        let group = &self.results[g];
+        group.results.get(i).map(|x| (&x.result, group.metadata.id.clone()))
+    }
 
-        for group in self.results.iter_mut() {
-            if cursor < group.results.len() {
-                return group.results.get(cursor).map(|x| (x, group.metadata.id.clone()));
-            } else {
-                cursor -= group.results.len();
-            }
-        }
-
-        None
-    }
-
-    pub fn iter_nice(&self, selected: bool, around: usize, unselected: usize) -> impl Iterator<Item = NiceIter> {
-        // iterate over all groups
-        // yield a NewSource every time the source changes
-        // yield the first 3 results from each group UNLESS we are selected AND the cursor is in that group, then yield the 7 results centered around the cursor (from the self.cursor_range() function)
-
-        // let mut vec = vec![];
-
-        // for (i, (y, result)) in self.groups.iter().flat_map(|g| g.results.iter().enumerate()).enumerate() {
-        //     if y == 0 {
-        //         vec.push(NiceIter::NewSource(result.source.copy()));
-        //     }
-        //     if selected {
-        //         // we have to handle the range checks
-        //     } else {
-        //         if y < 3 {
-        //             vec.push(NiceIter::Result { result, cursor_on: false });
-        //         }
-        //     }
-        // }
-
-        // vec.iter()
-
-        self.results
-            .iter()
-            .flat_map(|g| g.results.iter().map(|r| (r, g.metadata.clone())).enumerate())
-            .enumerate()
-            .flat_map(move |(i, (y, (result, group)))| {
-                let res = if selected {
-                    if self.cursor_range(around).contains(&i) {
-                        Some(NiceIter::Result {
-                            result,
-                            cursor_on: { self.cursor == i },
-                            index: i,
-                            // metadata: group.clone(),
-                        })
-                    } else {
-                        None
-                    }
-                } else if (unselected == 0) || (y < unselected) {
-                    Some(NiceIter::Result {
-                        result,
-                        cursor_on: false,
-                        index: i,
-                        // metadata: group.clone(),
-                    })
-                } else {
-                    None
-                };
+    // the other plugins that returned the result under the cursor
+    pub fn duplicates_from_cursor(&self) -> &[Duplicate] {
+        match self.position(self.cursor) {
+            Some((g, i)) => &self.results[g].results[i].also_from,
+            None => &[],
+        }
+    }
 
-                if y == 0 {
-                    if let Some(res) = res {
-                        vec![NiceIter::NewSource(group), res]
-                    } else {
-                        vec![NiceIter::NewSource(group)]
-                    }
-                } else if let Some(res) = res {
-                    vec![res]
-                } else {
-                    vec![]
-                }
+    // how many rows iter_nice has in total, so the scroll area can size itself without laying them out
+    pub fn nice_rows(&self, selected: bool, around: usize, unselected: usize) -> usize {
+        if self.ranking.is_some() {
+            return self.ranked_window(selected, around, unselected).len();
+        }
+        let around_cursor = if selected { Some(self.cursor_range(around)) } else { None };
+        (0..self.results.len())
+            .map(|g| {
+                let (shown, more) = self.shown_rows(g, &around_cursor, unselected);
+                1 + shown.len() + usize::from(more)
             })
+            .sum()
     }
-    fn cursor_range(&self, around: usize) -> std::ops::Range<usize> {
-        let mut start = self.cursor.saturating_sub(around);
-        let mut end = self.cursor.saturating_add(around + 1);
-        let mut range = start..end;
-        let mut last_source = "".to_owned();
-        for (x, (_y, g)) in self.results.iter().flat_map(|g| g.results.iter().map(|_| g.metadata.clone()).enumerate()).enumerate() {
-            if range.contains(&x) && last_source != g.raw_name {
-                last_source = g.raw_name.clone();
-                if x <= self.cursor {
-                    start = x;
-                } else {
-                    end = x;
-                    break;
-                }
+
+    // only the given rows out of nice_rows, groups before them are skipped by their row count without visiting their results
+    pub fn iter_nice(&self, selected: bool, around: usize, unselected: usize, rows: std::ops::Range<usize>) -> Box<dyn Iterator<Item = NiceIter<'_>> + '_> {
+        if let Some(ranking) = &self.ranking {
+            // one flat list, every row carries its source instead of having group headers
+            let window = self.ranked_window(selected, around, unselected);
+            let window = (window.start + rows.start).min(window.end)..(window.start + rows.end).min(window.end);
+            return Box::new(
+                ranking.order[window.clone()]
+                    .iter()
+                    .zip(window)
+                    .map(move |((g, r), i)| self.entry(*g, *r, i, selected && self.cursor == i, Some(self.results[*g].metadata.clone()))),
+            );
+        }
+
+        // every group yields its header, the rows that are shown, and a "show more" row once its last loaded row is shown
+        let around_cursor = if selected { Some(self.cursor_range(around)) } else { None };
+        Box::new(
+            (0..self.results.len())
+                // the first row of every group, stopping once a group starts past the wanted rows
+                .scan(0, move |first, g| {
+                    let (shown, more) = self.shown_rows(g, &around_cursor, unselected);
+                    let group_first = *first;
+                    *first += 1 + shown.len() + usize::from(more);
+                    (group_first < rows.end).then_some((g, group_first, shown, more))
+                })
+                .flat_map(move |(g, group_first, shown, more)| {
+                    let count = 1 + shown.len() + usize::from(more);
+                    let wanted = rows.start.saturating_sub(group_first).min(count)..rows.end.saturating_sub(group_first).min(count);
+                    let start = self.starts[g];
+                    wanted.map(move |row| match row {
+                        0 => self.header(g),
+                        row if row <= shown.len() => {
+                            let i = shown.start + row - 1;
+                            self.entry(g, i, start + i, selected && self.cursor == start + i, None)
+                        }
+                        _ => NiceIter::More {
+                            metadata: self.results[g].metadata.clone(),
+                            remaining: self.results[g].more.len(),
+                        },
+                    })
+                }),
+        )
+    }
+
+    fn ranked_window(&self, selected: bool, around: usize, unselected: usize) -> std::ops::Range<usize> {
+        let len = self.ranking.as_ref().map_or(0, |ranking| ranking.order.len());
+        let window = if selected {
+            self.cursor.saturating_sub(around)..self.cursor.saturating_add(around + 1)
+        } else if unselected == 0 {
+            0..len
+        } else {
+            0..unselected * self.results.len()
+        };
+        window.start.min(len)..window.end.min(len)
+    }
+
+    // the rows of a group that are shown, within the group, and whether a "show more" row follows them
+    fn shown_rows(&self, g: usize, around_cursor: &Option<std::ops::Range<usize>>, unselected: usize) -> (std::ops::Range<usize>, bool) {
+        let group = &self.results[g];
+        if self.collapsed.contains(&group.metadata.raw_name) {
+            return (0..0, false);
+        }
+        let start = self.starts[g];
+        let len = group.results.len();
+        let rows = match around_cursor {
+            Some(range) => range.start.clamp(start, start + len) - start..range.end.clamp(start, start + len) - start,
+            None if unselected == 0 => 0..len,
+            None => 0..unselected.min(len),
+        };
+        let more = !group.more.is_empty() && !rows.is_empty() && rows.end == len;
+        (rows, more)
+    }
+
+    // the header of the focused group and how many rows it has, counting the "show more" row
+    pub fn focus_header(&self) -> Option<(NiceIter<'_>, usize)> {
+        let range = self.focus_range()?;
+        let (g, _) = self.position(self.cursor)?;
+        Some((self.header(g), range.len() + usize::from(!self.results[g].more.is_empty())))
+    }
+
+    // the cursor's row within the focused group
+    pub fn focus_cursor(&self) -> Option<usize> {
+        self.focus_range().map(|range| self.cursor - range.start)
+    }
+
+    // only the rows of the focused group that the scroll area is showing
+    pub fn iter_focus(&self, rows: std::ops::Range<usize>) -> Box<dyn Iterator<Item = NiceIter<'_>> + '_> {
+        let (range, g) = match (self.focus_range(), self.position(self.cursor)) {
+            (Some(range), Some((g, _))) => (range, g),
+            _ => return Box::new(std::iter::empty()),
+        };
+        Box::new(rows.filter_map(move |row| {
+            let group = &self.results[g];
+            if row < range.len() {
+                Some(self.entry(g, row, range.start + row, self.cursor == range.start + row, None))
+            } else if row == range.len() && !group.more.is_empty() {
+                Some(NiceIter::More {
+                    metadata: group.metadata.clone(),
+                    remaining: group.more.len(),
+                })
+            } else {
+                None
             }
-            range = start..end;
+        }))
+    }
+
+    fn header(&self, g: usize) -> NiceIter<'_> {
+        let group = &self.results[g];
+        NiceIter::NewSource {
+            metadata: group.metadata.clone(),
+            num_results: group.results.len() + group.more.len(),
+            finished: group.finished,
+            collapsed: self.collapsed.contains(&group.metadata.raw_name),
         }
-        start..end
+    }
+
+    fn entry(&self, g: usize, i: usize, index: usize, cursor_on: bool, source: Option<Rc<SearchMetadata>>) -> NiceIter<'_> {
+        let group = &self.results[g];
+        let entry = &group.results[i];
+        NiceIter::Result {
+            result: &entry.result,
+            also_from: &entry.also_from,
+            marked: self.is_marked(&group.metadata.raw_name, &entry.result),
+            cursor_on,
+            index,
+            source,
+        }
+    }
+
+    // the rows around the cursor, never crossing into the groups next to it
+    fn cursor_range(&self, around: usize) -> std::ops::Range<usize> {
+        let group = match self.position(self.cursor) {
+            Some((g, _)) => self.group_range(g),
+            None => return 0..0,
+        };
+        self.cursor.saturating_sub(around).max(group.start)..self.cursor.saturating_add(around + 1).min(group.end)
     }
 }
 
+// the abi has no way for a plugin to say which of its results are the same thing as another plugin's, so until it does
+// results only count as duplicates when their title and full context are exactly the same, anything looser risks merging different results
+fn dedup_key(result: &SearchResult) -> (String, String) {
+    (result.title().to_string(), result.context().to_string())
+}
+
+fn same_result(a: &SearchResult, b: &SearchResult) -> bool {
+    a.title() == b.title() && a.context() == b.context()
+}
+
 pub struct ResultGroup {
-    results: Vec<SearchResult>,
+    results: Vec<ResultEntry>,
+    // results past the cap, they are not ranked, merged or drawn until the group is asked to show more
+    more: Vec<ResultEntry>,
     metadata: Rc<SearchMetadata>,
+    // set once the plugin has answered, a group from a stale cache stays unfinished until its refresh comes back
+    finished: bool,
+}
+
+impl ResultGroup {
+    fn push_capped(&mut self, entries: Vec<ResultEntry>, cap: usize) {
+        let room = match cap {
+            0 => usize::MAX,
+            // once results are waiting, new ones queue up behind them so the plugin's order is kept
+            _ if !self.more.is_empty() => 0,
+            _ => cap.saturating_sub(self.results.len()),
+        };
+        let mut entries = entries;
+        if entries.len() > room {
+            self.more.extend(entries.drain(room..));
+        }
+        self.results.extend(entries);
+    }
+}
+
+pub struct ResultEntry {
+    result: SearchResult,
+    // the same result returned by lower priority plugins, merged into this one when de-duplication is on
+    also_from: Vec<Duplicate>,
+    // the ranking score for the current query, None until ranked mode scores it
+    score: Option<f32>,
+}
+
+impl ResultEntry {
+    fn new(result: SearchResult) -> Self {
+        Self {
+            result,
+            also_from: Vec::new(),
+            score: None,
+        }
+    }
+}
+
+pub struct Duplicate {
+    pub metadata: Rc<SearchMetadata>,
+    pub result: SearchResult,
 }
 
 pub enum NiceIter<'a> {
-    NewSource(Rc<SearchMetadata>),
+    NewSource {
+        metadata: Rc<SearchMetadata>,
+        num_results: usize,
+        finished: bool,
+        collapsed: bool,
+    },
+    // a group has more results than it shows
+    More {
+        metadata: Rc<SearchMetadata>,
+        remaining: usize,
+    },
     Result {
         result: &'a SearchResult,
+        also_from: &'a [Duplicate],
+        marked: bool,
         cursor_on: bool,
         index: usize,
+        // only set in ranked mode, where there are no group headers to show the source
+        source: Option<Rc<SearchMetadata>>,
         // metadata: Rc<SearchMetadata>,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(name: &str, priority: u32) -> SearchMetadata {
+        SearchMetadata {
+            pretty_name: egui::text::LayoutJob::default(),
+            priority,
+            raw_name: name.to_string(),
+            id: PluginId { filename: name.into() },
+            icon: None,
+        }
+    }
+
+    fn ranked_titles(holder: &ResultHolder) -> Vec<(String, String)> {
+        (0..holder.len())
+            .filter_map(|index| holder.position(index))
+            .map(|(g, i)| (holder.results[g].metadata.raw_name.clone(), holder.results[g].results[i].result.title().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn priority_lifts_an_equally_good_match() {
+        let mut holder = ResultHolder::default();
+        holder.set_ranking(true, "notes");
+        holder.add_results(vec![SearchResult::new("notes")], metadata("low", 0));
+        holder.add_results(vec![SearchResult::new("notes")], metadata("high", 50));
+        assert_eq!(ranked_titles(&holder)[0].0, "high");
+    }
+
+    #[test]
+    fn priority_doesnt_beat_a_clearly_better_match() {
+        let mut holder = ResultHolder::default();
+        holder.set_ranking(true, "notes");
+        holder.add_results(vec![SearchResult::new("xnxoxtxexs"), SearchResult::new("unrelated")], metadata("high", 128));
+        holder.add_results(vec![SearchResult::new("notes")], metadata("low", 0));
+        let titles = ranked_titles(&holder);
+        assert_eq!(titles[0], ("low".to_string(), "notes".to_string()));
+        assert_eq!(titles[2], ("high".to_string(), "unrelated".to_string()));
+    }
+
+    #[test]
+    fn only_identical_results_are_merged() {
+        let mut holder = ResultHolder::default();
+        holder.set_dedup(true);
+        holder.add_results(vec![SearchResult::new("notes.txt").set_context("C:/a/notes.txt")], metadata("files", 10));
+        holder.add_results(
+            vec![
+                SearchResult::new("notes.txt").set_context("C:/a/notes.txt"),
+                SearchResult::new("notes.txt").set_context("C:/b/notes.txt"),
+                SearchResult::new("Notes.txt").set_context("C:/a/notes.txt"),
+                SearchResult::new("notes.txt"),
+            ],
+            metadata("recent", 0),
+        );
+        assert_eq!(holder.len(), 4);
+        assert_eq!(holder.results[0].results[0].also_from.len(), 1);
+        assert_eq!(holder.results[1].results.len(), 3);
+    }
+
+    #[test]
+    fn jumps_move_between_groups() {
+        let mut holder = ResultHolder::default();
+        holder.add_results(vec![SearchResult::new("a1"), SearchResult::new("a2")], metadata("a", 30));
+        holder.add_results(vec![], metadata("empty", 20));
+        holder.add_results(vec![SearchResult::new("b1"), SearchResult::new("b2"), SearchResult::new("b3")], metadata("b", 10));
+        holder.raw_set_cursor(1);
+        holder.jump_forward(true);
+        assert_eq!(holder.cursor(), 2);
+        holder.jump_forward(true);
+        assert_eq!(holder.cursor(), 0);
+        holder.jump_backward(true);
+        assert_eq!(holder.cursor(), 4);
+        holder.jump_backward(true);
+        assert_eq!(holder.cursor(), 1);
+        holder.jump_backward(false);
+        assert_eq!(holder.cursor(), 4);
+    }
+}
@@ -0,0 +1,74 @@
+use egui::{Align2, Pos2, Vec2};
+
+use crate::config::{BarAnchor, Config, ResultsPosition};
+
+// where the overlay's windows go, worked out once a frame from the config and the screen size in points
+pub struct Layout {
+    screen: Vec2,
+    center: Pos2,
+    gap: f32,
+    width: Option<f32>,
+    max_height: f32,
+    below: bool,
+}
+
+impl Layout {
+    pub fn new(config: &Config, screen: Vec2) -> Self {
+        let center = match config.bar_anchor {
+            BarAnchor::Center => Pos2::new(screen.x / 2.0, screen.y / 2.0),
+            BarAnchor::TopThird => Pos2::new(screen.x / 2.0, screen.y / 3.0),
+            BarAnchor::Custom => Pos2::new(screen.x / 2.0 + config.bar_offset_x, screen.y / 2.0 + config.bar_offset_y),
+        };
+        Layout {
+            screen,
+            center,
+            gap: config.gap_between_search_bar_and_results,
+            width: (config.bar_width > 0.0).then_some(config.bar_width),
+            max_height: config.results_max_height,
+            below: config.results_position == ResultsPosition::Below,
+        }
+    }
+
+    pub fn bar_center(&self) -> Pos2 {
+        self.center
+    }
+
+    // None when the bar sizes itself to its contents
+    pub fn width(&self) -> Option<f32> {
+        self.width
+    }
+
+    // the pivot and position of the results window
+    pub fn results(&self, bar_height: f32) -> (Align2, Pos2) {
+        self.side(bar_height, self.below)
+    }
+
+    // the pivot and position of windows on the side of the bar the results aren't on, like history search and the full result
+    pub fn opposite(&self, bar_height: f32) -> (Align2, Pos2) {
+        self.side(bar_height, !self.below)
+    }
+
+    // the results scroll inside the space between the bar and the edge of the screen instead of running off it
+    pub fn results_max_height(&self, bar_height: f32) -> f32 {
+        let available = if self.below {
+            self.screen.y - self.center.y - bar_height / 2.0 - self.gap * 2.0
+        } else {
+            self.center.y - bar_height / 2.0 - self.gap * 2.0
+        };
+        let available = available.max(100.0);
+        if self.max_height > 0.0 {
+            available.min(self.max_height)
+        } else {
+            available
+        }
+    }
+
+    fn side(&self, bar_height: f32, below: bool) -> (Align2, Pos2) {
+        let offset = bar_height / 2.0 + self.gap;
+        if below {
+            (Align2::CENTER_TOP, Pos2::new(self.center.x, self.center.y + offset))
+        } else {
+            (Align2::CENTER_BOTTOM, Pos2::new(self.center.x, self.center.y - offset))
+        }
+    }
+}
@@ -1,23 +1,45 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use egui::RichText;
 use quick_search_lib::SearchResult;
+mod fallback;
 mod holder;
-use crate::config::ConfigLock;
+mod layout;
+mod ranking;
+mod status;
+use crate::config::{ConfigLock, DisplayMode, PreviewPlacement};
 
 use self::holder::NiceIter;
+use self::layout::Layout;
 
 use crate::LOGGER;
 use quick_search_lib::Log;
 
-use super::{PluginLoadResult, SearchMetadata};
+use super::{
+    actions::{self, ResultAction},
+    cache::CacheLookup,
+    execute_in_order,
+    icons::{IconCache, ResultIcon},
+    placement::{self, Placement},
+    preview::{show_markdown, PreviewBlock, PreviewKey},
+    query::SearchQuery,
+    ExecuteJob, FontLoader, KeyAction, Keymap, PluginLoadResult, SearchEvent, SearchMessage, SearchMetadata, SearchSink,
+};
 use holder::ResultHolder;
+use status::{show_status, PluginStatus, StatusView};
+
+// the height egui's default separator takes up
+const SEPARATOR_SPACING: f32 = 6.0;
+
+const SEARCH_INPUT_ID: &str = "search_input";
 
 pub struct App<'a> {
     config_lock: ConfigLock<'a>,
 
     audio: Option<rusty_audio::Audio>,
-    size: Option<egui::Vec2>,
+    placement: Option<Placement>,
+    // where the window that had focus before the overlay opened is, for MonitorPlacement::FocusedWindow
+    focused_window: Option<(i32, i32)>,
     positioned: bool,
 
     // search
@@ -41,13 +63,63 @@ pub struct App<'a> {
     time: std::time::Instant,
 
     searchholder: SearchHolder,
+
+    // secondary actions for the result under the cursor
+    action_menu: Option<ActionMenu>,
+    cursor_rect: Option<egui::Rect>,
+
+    // the row under the mouse last frame, and whether the mouse was over the results window, which makes the wheel move the cursor
+    hovered: Option<usize>,
+    pointer_over_results: bool,
+
+    icons: IconCache,
+    fonts: FontLoader,
+
+    preview: Option<PreviewState>,
+    previews: HashMap<PreviewKey, Vec<PreviewBlock>>,
+    preview_images: IconCache,
+
+    // the result that was drawn under the cursor, what enter executes
+    highlighted: Option<(SearchResult, quick_search_lib::PluginId)>,
+
+    // position in the query history while cycling with alt+up/down, and what was typed before cycling started
+    history_index: Option<usize>,
+    history_draft: String,
+    history_search: Option<HistorySearch>,
+
+    // the cursor the focused group was last scrolled to, so the scroll area only follows the cursor when it moves
+    scrolled_to: Option<usize>,
+    focus_offset: f32,
+}
+
+// ctrl+r reverse incremental search over the query history
+struct HistorySearch {
+    needle: String,
+    found: Option<(usize, String)>,
+}
+
+// the preview follows the cursor, and is only requested once the cursor has rested on a result for the preview delay
+struct PreviewState {
+    key: PreviewKey,
+    since: std::time::Instant,
+    pending: Option<crossbeam::channel::Receiver<Vec<PreviewBlock>>>,
+    content: Option<Vec<PreviewBlock>>,
+}
+
+struct ActionMenu {
+    actions: Vec<ResultAction>,
+    selected: usize,
 }
 
 impl App<'_> {
     pub fn new(loadresults: PluginLoadResult) -> Self {
         let config_lock = crate::CONFIG_FILE.lock();
+        let mut searchholder = SearchHolder::new(loadresults);
+        searchholder
+            .results
+            .set_collapsed(config_lock.get().plugin_states.iter().filter(|(_, state)| state.collapsed).map(|(name, _)| name.clone()));
         Self {
-            searchholder: SearchHolder::new(loadresults),
+            searchholder,
             audio: if config_lock.get().audio_enabled {
                 let mut audio = rusty_audio::Audio::new();
                 audio.add("notif", crate::AUDIO_FILE_PATH.clone());
@@ -56,7 +128,8 @@ impl App<'_> {
                 None
             },
             config_lock,
-            size: None,
+            placement: None,
+            focused_window: placement::foreground_center(),
             positioned: bool::default(),
             input: String::default(),
             selected: bool::default(),
@@ -71,7 +144,348 @@ impl App<'_> {
             force_redraw_now: bool::default(),
             passthrough: bool::default(),
             time: std::time::Instant::now(),
+            action_menu: None,
+            cursor_rect: None,
+            hovered: None,
+            pointer_over_results: false,
+            icons: IconCache::default(),
+            fonts: FontLoader::default(),
+            preview: None,
+            previews: HashMap::new(),
+            preview_images: IconCache::new(&crate::PREVIEW_POOL, "preview images", 256),
+            highlighted: None,
+            history_index: None,
+            history_draft: String::default(),
+            history_search: None,
+            scrolled_to: None,
+            focus_offset: 0.0,
+        }
+    }
+
+    // collapsing is remembered per plugin, the fallback group has no plugin config to remember it in
+    fn remember_collapsed(&mut self, name: &str, collapsed: bool) {
+        if self.config_lock.get().get_plugin(name).map(|state| state.collapsed != collapsed).unwrap_or(false) {
+            if let Some(state) = self.config_lock.get_mut().plugin_states.get_mut(name) {
+                state.collapsed = collapsed;
+            }
+        }
+    }
+
+    fn set_input(&mut self, input: String, egui_context: &egui::Context) {
+        self.input = input;
+        self.searchholder.input_changed();
+        self.doubledown = false;
+        self.doubleup = false;
+        // move the text cursor to the end, otherwise it stays wherever it was in the old input
+        let id = egui::Id::new(SEARCH_INPUT_ID);
+        if let Some(mut state) = egui::TextEdit::load_state(egui_context, id) {
+            let end = egui::text::CCursor::new(self.input.chars().count());
+            state.cursor.set_char_range(Some(egui::text::CCursorRange::one(end)));
+            state.store(egui_context, id);
+        }
+    }
+
+    fn remember_query(&self) {
+        let config = self.config_lock.get();
+        if config.query_history_enabled {
+            crate::QUERY_HISTORY.push(&self.input, config.query_history_size);
+        }
+    }
+
+    // handles the history keys before the search bar sees them
+    fn history_input(&mut self, egui_context: &egui::Context, keymap: &Keymap) {
+        if !self.config_lock.get().query_history_enabled {
+            self.history_search = None;
+            return;
+        }
+
+        if let Some(search) = &mut self.history_search {
+            let mut accept = false;
+            let mut cancel = false;
+            let mut changed = false;
+            let mut next = false;
+            egui_context.input_mut(|i| {
+                if keymap.consume(i, KeyAction::HistorySearch, true) {
+                    next = true;
+                }
+                if keymap.consume(i, KeyAction::Execute, true) {
+                    accept = true;
+                }
+                if keymap.consume(i, KeyAction::Close, true) {
+                    cancel = true;
+                }
+                if i.consume_key(egui::Modifiers::NONE, egui::Key::Backspace) {
+                    changed = search.needle.pop().is_some();
+                }
+                // typed text goes to the needle instead of the search bar, and the popup is modal,
+                // so keys it doesn't use never reach the results cursor or the search bar while it is open
+                i.events.retain(|e| match e {
+                    egui::Event::Text(text) => {
+                        search.needle.push_str(text);
+                        changed = true;
+                        false
+                    }
+                    egui::Event::Key { pressed: true, .. } => false,
+                    _ => true,
+                });
+            });
+            if changed {
+                search.found = crate::QUERY_HISTORY.find(&search.needle, 0);
+            } else if next {
+                let start = search.found.as_ref().map(|(i, _)| i + 1).unwrap_or(0);
+                if let Some(found) = crate::QUERY_HISTORY.find(&search.needle, start) {
+                    search.found = Some(found);
+                }
+            }
+            if accept {
+                let found = search.found.take();
+                self.history_search = None;
+                if let Some((_, query)) = found {
+                    self.set_input(query, egui_context);
+                }
+            } else if cancel {
+                self.history_search = None;
+            }
+            return;
+        }
+
+        let mut open_search = false;
+        let mut step = 0i32;
+        let typing = !self.scrolling;
+        egui_context.input_mut(|i| {
+            if keymap.consume(i, KeyAction::HistorySearch, typing) {
+                open_search = true;
+            }
+            if keymap.consume(i, KeyAction::HistoryPrev, typing) {
+                step = 1;
+            }
+            if keymap.consume(i, KeyAction::HistoryNext, typing) {
+                step = -1;
+            }
+        });
+
+        if open_search {
+            LOGGER.trace("opening history search");
+            self.history_search = Some(HistorySearch {
+                needle: String::new(),
+                found: crate::QUERY_HISTORY.find("", 0),
+            });
+            return;
+        }
+
+        match (self.history_index, step) {
+            (_, 0) => {}
+            (None, 1) => {
+                if let Some(query) = crate::QUERY_HISTORY.get(0) {
+                    self.history_draft = self.input.clone();
+                    self.history_index = Some(0);
+                    self.set_input(query, egui_context);
+                }
+            }
+            (Some(index), 1) => {
+                if let Some(query) = crate::QUERY_HISTORY.get(index + 1) {
+                    self.history_index = Some(index + 1);
+                    self.set_input(query, egui_context);
+                }
+            }
+            (Some(0), _) => {
+                self.history_index = None;
+                let draft = std::mem::take(&mut self.history_draft);
+                self.set_input(draft, egui_context);
+            }
+            (Some(index), _) => {
+                if let Some(query) = crate::QUERY_HISTORY.get(index - 1) {
+                    self.history_index = Some(index - 1);
+                    self.set_input(query, egui_context);
+                }
+            }
+            (None, _) => {}
+        }
+    }
+
+    fn update_preview(&mut self) {
+        let cursor = self.searchholder.results.get_from_cursor();
+        let (result, plugin) = match cursor.and_then(|(result, plugin_id)| self.searchholder.loadresults.plugins.iter().find(|p| p.id == plugin_id).map(|plugin| (result, plugin))) {
+            Some(found) => found,
+            None => {
+                self.preview = None;
+                return;
+            }
+        };
+        let key = plugin.preview_key(result);
+        let delay = self.config_lock.get().preview_delay as u128;
+        match &mut self.preview {
+            Some(preview) if preview.key == key => {
+                if preview.pending.is_none() && preview.content.is_none() && preview.since.elapsed().as_millis() >= delay {
+                    LOGGER.trace(&format!("requesting preview for {}", key.1));
+                    preview.pending = Some(plugin.preview_pooled(result));
+                }
+                if let Some(pending) = &preview.pending {
+                    match pending.try_recv() {
+                        Ok(content) => {
+                            self.previews.insert(key, content.clone());
+                            preview.content = Some(content);
+                            preview.pending = None;
+                            self.force_redraw_now = true;
+                        }
+                        Err(crossbeam::channel::TryRecvError::Disconnected) => preview.pending = None,
+                        Err(crossbeam::channel::TryRecvError::Empty) => {}
+                    }
+                }
+            }
+            _ => {
+                let content = self.previews.get(&key).cloned();
+                self.preview = Some(PreviewState {
+                    key,
+                    since: std::time::Instant::now(),
+                    pending: None,
+                    content,
+                });
+            }
+        }
+    }
+
+    fn open_action_menu(&mut self) {
+        if let Some((result, plugin_id)) = self.searchholder.results.get_from_cursor() {
+            if let Some(plugin) = self.searchholder.loadresults.plugins.iter().find(|p| p.id == plugin_id) {
+                let mut actions = plugin.actions(result);
+                // merged duplicates can still be opened by the plugin that returned them
+                actions.extend(self.searchholder.results.duplicates_from_cursor().iter().map(|d| ResultAction::execute_with(&d.metadata.raw_name)));
+                self.action_menu = Some(ActionMenu { actions, selected: 0 });
+            }
+        }
+    }
+
+    // runs the plugin's execute for a result, or opens it if it is a fallback
+    fn execute(&self, result: &SearchResult, plugin_id: &quick_search_lib::PluginId) {
+        if *plugin_id == fallback::plugin_id() {
+            fallback::execute(result);
+            return;
+        }
+        for plugin in &self.searchholder.loadresults.plugins {
+            if plugin.id == *plugin_id {
+                if self.config_lock.get().frecency_enabled {
+                    crate::USAGE_STORE.record(plugin.name, result, &SearchQuery::parse(&self.input).text());
+                }
+                plugin.execute(result);
+            }
+        }
+    }
+
+    // several marked results run one after another in the order they are shown, on a single thread so the window doesn't have to wait, a single one runs like before
+    fn execute_all(&self, targets: &[(SearchResult, quick_search_lib::PluginId)]) {
+        if let [(result, plugin_id)] = targets {
+            self.execute(result, plugin_id);
+            return;
         }
+        let mut jobs: Vec<ExecuteJob> = Vec::with_capacity(targets.len());
+        for (result, plugin_id) in targets {
+            if *plugin_id == fallback::plugin_id() {
+                let result = result.clone();
+                jobs.push(Box::new(move || fallback::execute(&result)));
+                continue;
+            }
+            match self.searchholder.loadresults.plugins.iter().find(|p| p.id == *plugin_id) {
+                Some(plugin) => {
+                    if self.config_lock.get().frecency_enabled {
+                        crate::USAGE_STORE.record(plugin.name, result, &SearchQuery::parse(&self.input).text());
+                    }
+                    jobs.push(plugin.execute_job(result));
+                }
+                None => LOGGER.warn(&format!("no plugin left to execute {}", result.title())),
+            }
+        }
+        execute_in_order(jobs);
+    }
+
+    fn run_action(&mut self, id: &str, egui_context: &egui::Context) {
+        if let Some(name) = id.strip_prefix(actions::EXECUTE_WITH) {
+            if let Some(duplicate) = self.searchholder.results.duplicates_from_cursor().iter().find(|d| d.metadata.raw_name == name) {
+                self.execute(&duplicate.result, &duplicate.metadata.id);
+            }
+        } else if let Some((result, plugin_id)) = self.searchholder.results.get_from_cursor() {
+            if id == actions::EXECUTE {
+                self.execute(result, &plugin_id);
+            } else {
+                for plugin in &self.searchholder.loadresults.plugins {
+                    if plugin.id == plugin_id {
+                        plugin.run_action(id, result, egui_context);
+                    }
+                }
+            }
+        }
+        self.remember_query();
+    }
+
+    // the keyboard paths hand focus back with the search bar's response, the mouse paths run after the bar is drawn and go through its id
+    fn enter_results_with_mouse(&mut self, egui_context: &egui::Context) {
+        self.scrolling = true;
+        self.doubledown = false;
+        self.doubleup = false;
+        // otherwise keys bound to plain letters would also type into the query
+        egui_context.memory_mut(|m| m.surrender_focus(egui::Id::new(SEARCH_INPUT_ID)));
+    }
+
+    // moves the cursor onto the result the user most likely wants, used when entering scrolling mode
+    fn preselect(&mut self) {
+        let config = self.config_lock.get();
+        if !config.frecency_enabled || !config.frecency_preselect {
+            return;
+        }
+        let query = SearchQuery::parse(&self.input).text();
+        let scorer = crate::USAGE_STORE.scorer(&query);
+        if let Some(index) = self.searchholder.results.best_result(|plugin, result| scorer.score(plugin, result)) {
+            LOGGER.trace(&format!("preselecting result {}", index));
+            self.searchholder.results.raw_set_cursor(index);
+        }
+    }
+
+    // handles the keys for the action menu before the search bar sees them, returns true if an action was run and the window should close
+    fn action_menu_input(&mut self, egui_context: &egui::Context, keymap: &Keymap) -> bool {
+        let menu = match &mut self.action_menu {
+            Some(menu) => menu,
+            None => {
+                if self.scrolling && egui_context.input_mut(|i| keymap.consume(i, KeyAction::ActionMenu, false)) {
+                    LOGGER.trace("action menu key pressed!");
+                    self.open_action_menu();
+                }
+                return false;
+            }
+        };
+
+        let mut close_menu = false;
+        let mut run = None;
+        egui_context.input_mut(|i| {
+            if keymap.consume(i, KeyAction::Close, false) {
+                close_menu = true;
+            }
+            if keymap.consume(i, KeyAction::Next, false) {
+                menu.selected = (menu.selected + 1) % menu.actions.len().max(1);
+            }
+            if keymap.consume(i, KeyAction::Prev, false) {
+                menu.selected = menu.selected.checked_sub(1).unwrap_or(menu.actions.len().saturating_sub(1));
+            }
+            if keymap.consume(i, KeyAction::Execute, false) {
+                run = menu.actions.get(menu.selected).map(|a| a.id.clone());
+            }
+            for action in menu.actions.iter() {
+                if let Some(key) = action.shortcut {
+                    if i.consume_key(egui::Modifiers::NONE, key) {
+                        run = Some(action.id.clone());
+                    }
+                }
+            }
+        });
+
+        if close_menu {
+            self.action_menu = None;
+        }
+        if let Some(id) = run {
+            self.action_menu = None;
+            self.run_action(&id, egui_context);
+            return true;
+        }
+        false
     }
     // pub fn try_dispatch_search(&mut self) -> anyhow::Result<()> {
     //     // check old handles
@@ -161,14 +575,14 @@ impl egui_overlay::EguiOverlay for App<'_> {
         _default_gfx_backend: &mut egui_overlay::egui_render_three_d::ThreeDBackend,
         glfw_backend: &mut egui_overlay::egui_window_glfw_passthrough::GlfwBackend,
     ) {
-        if self.size.is_none() {
+        if self.placement.is_none() {
             glfw_backend.glfw.with_connected_monitors(|_glfw, monitors| {
-                let monitor = monitors.first();
-                match monitor {
+                let config = self.config_lock.get();
+                match placement::pick(monitors, config.monitor_placement, &config.monitor_name, self.focused_window) {
                     None => {
                         LOGGER.error("no monitor");
                     }
-                    Some(monitor) => {
+                    Some(placement) => {
                         // this code will literally only run once so we're gonna also request focus
                         // unsafe {
                         //     let window_ptr = egui_overlay::egui_window_glfw_passthrough::glfw::Context::window_ptr(&glfw_backend.window);
@@ -218,39 +632,51 @@ impl egui_overlay::EguiOverlay for App<'_> {
                         //     *v = Some(Vec2::new(x as f32, y as f32));
                         // } // THIS SCREWED UP MY MONITOR LOL
 
-                        let (x1, y1, x2, y2) = monitor.get_workarea();
-                        LOGGER.info(&format!("monitor workarea: {}x{} {}x{}", x1, y1, x2, y2));
-                        self.size = Some(egui::Vec2::new(x2 as f32, y2 as f32));
+                        self.placement = Some(placement);
                     }
                 }
             });
         } else if self.time.elapsed().as_millis() > self.config_lock.get().appearance_delay as u128 {
-            if let Some(size) = self.size {
+            if let Some(placement) = &self.placement {
                 if !self.positioned {
-                    glfw_backend.window.set_pos(0, 0);
-                    glfw_backend.window.set_size(size.x as i32 - 1, size.y as i32 - 1);
+                    glfw_backend.window.set_pos(placement.x, placement.y);
+                    glfw_backend.window.set_size(placement.width - 1, placement.height - 1);
                     self.positioned = true;
                 }
+                egui_context.set_pixels_per_point(placement.scale);
             }
 
-            let (midwindowx, midwindowy) = {
+            // the window size is in pixels, egui lays out in points
+            let layout = {
                 let (x, y) = glfw_backend.window.get_size();
-                (x / 2, y / 2)
+                let scale = egui_context.pixels_per_point();
+                Layout::new(self.config_lock.get(), egui::Vec2::new(x as f32 / scale, y as f32 / scale))
             };
 
-            egui_context.set_visuals({
-                let mut visuals = egui::Visuals::dark();
-                visuals.popup_shadow.extrusion = 0.0;
-                visuals.window_shadow.extrusion = 0.0;
-                visuals
-            });
+            self.fonts.apply(egui_context, &self.config_lock.get().fonts);
+            egui_context.set_visuals(self.config_lock.get().theme.visuals());
+
+            // cloned so the handlers below can borrow self mutably
+            let keymap = self.config_lock.get().keymap.clone();
+            let pressed = |action: KeyAction, typing: bool| egui_context.input_mut(|i| keymap.consume(i, action, typing));
+
+            if self.action_menu_input(egui_context, &keymap) {
+                glfw_backend.window.set_should_close(true);
+            }
+            self.history_input(egui_context, &keymap);
 
-            let barheight = egui::Window::new("Search")
+            let bar = egui::Window::new("Search")
                 .title_bar(false)
                 // .fixed_pos(Pos2::new(midwindowx as f32 - 200., midwindowy as f32 - 30.))
                 // .fixed_size(Vec2::new(400., 60.))
                 .resizable(false)
-                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::new(0., 0.))
+                .pivot(egui::Align2::CENTER_CENTER)
+                .fixed_pos(layout.bar_center());
+            let bar = match layout.width() {
+                Some(width) => bar.min_width(width).max_width(width),
+                None => bar,
+            };
+            let barheight = bar
                 .show(egui_context, |ui| {
                     let r = ui
                         .vertical_centered(|ui| {
@@ -264,18 +690,22 @@ impl egui_overlay::EguiOverlay for App<'_> {
                                                 .to_string(),
                                         )
                                         .size(self.config_lock.get().time_font_size)
-                                        .color(egui::Color32::LIGHT_RED),
+                                        .color(self.config_lock.get().theme.clock()),
                                     )
                                     .wrap(false),
                                 );
                                 ui.separator();
                             }
 
-                            let textinput = egui::TextEdit::singleline(&mut self.input).vertical_align(egui::Align::Center).horizontal_align(egui::Align::Center);
+                            let textinput = egui::TextEdit::singleline(&mut self.input)
+                                .id(egui::Id::new(SEARCH_INPUT_ID))
+                                .font(egui::FontId::proportional(self.config_lock.get().fonts.search_input_size))
+                                .vertical_align(egui::Align::Center)
+                                .horizontal_align(egui::Align::Center);
 
                             let mut size = ui.available_size();
 
-                            size.y = size.y.min(20.0);
+                            size.y = size.y.min(self.config_lock.get().fonts.search_input_size + 7.5);
                             // size.x = size.x.min(20.0);
 
                             ui.add_sized(size, textinput)
@@ -293,6 +723,9 @@ impl egui_overlay::EguiOverlay for App<'_> {
 
                     // if self.joinhandles.is_empty() {
                     // }
+                    self.searchholder.results.hold_layout(self.scrolling);
+                    self.searchholder.results.set_dedup(self.config_lock.get().dedup_results);
+                    self.searchholder.results.set_result_cap(self.config_lock.get().results_per_plugin);
                     self.searchholder.dispatch(&self.config_lock, &self.input);
 
                     if r.changed() {
@@ -304,6 +737,8 @@ impl egui_overlay::EguiOverlay for App<'_> {
                         self.searchholder.input_changed();
                         self.doubledown = false;
                         self.doubleup = false;
+                        // typing over a recalled query starts a new one
+                        self.history_index = None;
                     }
                     // } else if let Some(changed) = self.last_changed {
                     //     // if it has been x ms since the last change, then dispatch the search and set last_changed to None
@@ -316,9 +751,45 @@ impl egui_overlay::EguiOverlay for App<'_> {
                     //     }
                     // }
 
-                    if egui_context.input(|i| i.key_pressed(egui::Key::ArrowDown)) || (egui_context.input(|i| i.raw_scroll_delta.y < 0.0) && self.scrolling) {
-                        LOGGER.trace("arrow down pressed!");
+                    let typing = !self.scrolling;
+
+                    if pressed(KeyAction::ClearInput, typing) {
+                        LOGGER.trace("clear input pressed!");
+                        self.set_input(String::new(), egui_context);
+                        self.history_index = None;
+                        self.scrolling = false;
+                        r.request_focus();
+                    }
+
+                    if self.scrolling && pressed(KeyAction::ShowMore, false) {
+                        LOGGER.trace("show more pressed!");
+                        if let Some(name) = self.searchholder.results.group_at_cursor() {
+                            self.searchholder.results.show_more(&name);
+                        }
+                    }
 
+                    // the wheel moves the cursor while scrolling, or when it is turned over the results, which enters them first
+                    let wheel = if self.scrolling || self.pointer_over_results {
+                        egui_context.input(|i| i.raw_scroll_delta.y)
+                    } else {
+                        0.0
+                    };
+                    if wheel != 0.0 && !self.scrolling {
+                        self.scrolling = true;
+                        self.doubledown = false;
+                        self.doubleup = false;
+                        r.surrender_focus();
+                        self.searchholder.results.clear_cursor();
+                        if wheel < 0.0 {
+                            // so the step below lands on the first result
+                            self.searchholder.results.decrement_cursor();
+                        }
+                    }
+
+                    if pressed(KeyAction::Next, typing) || wheel < 0.0 {
+                        LOGGER.trace("next pressed!");
+
+                        let mut entered_scrolling = false;
                         if self.doubledown {
                             self.doubledown = false;
                             self.scrolling = true;
@@ -326,11 +797,15 @@ impl egui_overlay::EguiOverlay for App<'_> {
                             // self.index = self.results.len().saturating_sub(1);
                             self.searchholder.results.clear_cursor();
                             self.searchholder.results.decrement_cursor();
+                            entered_scrolling = true;
                         }
 
                         if self.scrolling {
                             // self.index += 1;
                             self.searchholder.results.increment_cursor();
+                            if entered_scrolling {
+                                self.preselect();
+                            }
                             // if self.index >= self.results.len() {
                             //     self.index = 0;
                             // }
@@ -339,8 +814,8 @@ impl egui_overlay::EguiOverlay for App<'_> {
                         }
                     }
 
-                    if egui_context.input(|i| i.key_pressed(egui::Key::ArrowUp)) || (egui_context.input(|i| i.raw_scroll_delta.y > 0.0) && self.scrolling) {
-                        LOGGER.trace("arrow up pressed!");
+                    if pressed(KeyAction::Prev, typing) || wheel > 0.0 {
+                        LOGGER.trace("prev pressed!");
 
                         if self.doubleup {
                             self.doubleup = false;
@@ -363,31 +838,69 @@ impl egui_overlay::EguiOverlay for App<'_> {
                         }
                     }
 
-                    if egui_context.input(|i| i.key_pressed(egui::Key::PageUp)) {
-                        LOGGER.trace("page up pressed!");
+                    if pressed(KeyAction::PrevGroup, typing) {
+                        LOGGER.trace("prev group pressed!");
                         self.searchholder.results.jump_backward(self.scrolling);
                         self.scrolling = true;
+                        r.surrender_focus();
                     }
 
-                    if egui_context.input(|i| i.key_pressed(egui::Key::PageDown)) {
-                        LOGGER.trace("page down pressed!");
+                    if pressed(KeyAction::NextGroup, typing) {
+                        LOGGER.trace("next group pressed!");
                         self.searchholder.results.jump_forward(self.scrolling);
                         self.scrolling = true;
+                        r.surrender_focus();
+                    }
+
+                    if self.scrolling && pressed(KeyAction::Mark, false) {
+                        LOGGER.trace("mark pressed!");
+                        self.searchholder.results.toggle_mark_at_cursor();
+                    }
+
+                    if self.scrolling && pressed(KeyAction::Collapse, false) {
+                        LOGGER.trace("collapse pressed!");
+                        if let Some(name) = self.searchholder.results.collapse_at_cursor() {
+                            self.remember_collapsed(&name, true);
+                        }
                     }
 
-                    if egui_context.input(|i| i.key_pressed(egui::Key::Tab)) {
-                        LOGGER.trace("Tab pressed!");
+                    if self.scrolling && pressed(KeyAction::Expand, false) {
+                        LOGGER.trace("expand pressed!");
+                        if let Some(name) = self.searchholder.results.expand_next_collapsed() {
+                            self.remember_collapsed(&name, false);
+                        }
+                    }
+
+                    if self.scrolling && pressed(KeyAction::FocusGroup, false) {
+                        LOGGER.trace("focus group pressed!");
+                        self.searchholder.results.toggle_focus();
+                        self.scrolled_to = None;
+                    }
+
+                    if pressed(KeyAction::ToggleFocus, typing) {
+                        LOGGER.trace("toggle focus pressed!");
                         self.scrolling = !self.scrolling;
                         if self.scrolling {
                             // self.index = 0;
+                            // keys that type text are bound to actions while scrolling, so the search bar must not see them
+                            r.surrender_focus();
                             self.searchholder.results.clear_cursor();
+                            self.preselect();
                         } else {
                             r.request_focus();
                         }
                     }
 
-                    if egui_context.input(|i| i.key_pressed(egui::Key::Enter)) {
-                        LOGGER.trace("enter pressed!");
+                    if self.scrolling && pressed(KeyAction::AltAction, false) {
+                        LOGGER.trace("secondary action pressed!");
+                        if let Some(id) = self.searchholder.results.get_from_cursor().map(|(result, _)| actions::secondary(result)) {
+                            self.run_action(id, egui_context);
+                            glfw_backend.window.set_should_close(true);
+                        }
+                    }
+
+                    if pressed(KeyAction::Execute, typing) {
+                        LOGGER.trace("execute pressed!");
 
                         if !self.scrolling {
                             // if the text lost focus, we should switch to scrolling mode
@@ -397,44 +910,104 @@ impl egui_overlay::EguiOverlay for App<'_> {
                         } else {
                             // if enter was pressed while scrolling, we should use the selected result and close the window
                             // get result at index and call action
-                            if let Some((result, plugin_id)) = self.searchholder.results.get_from_cursor() {
-                                for plugin in &self.searchholder.loadresults.plugins {
-                                    if plugin.id == plugin_id {
-                                        plugin.execute(result);
-                                    }
-                                }
+                            // marked results are executed in the order they are shown, otherwise
+                            // execute what was drawn under the cursor last frame, even if results arrived since
+                            let mut targets = self.searchholder.results.marked();
+                            if targets.is_empty() {
+                                targets.extend(
+                                    self.highlighted
+                                        .clone()
+                                        .or_else(|| self.searchholder.results.get_from_cursor().map(|(result, id)| (result.clone(), id))),
+                                );
                             }
+                            self.execute_all(&targets);
+                            self.remember_query();
 
                             // close the window
                             glfw_backend.window.set_should_close(true);
                         }
                     }
 
-                    if egui_context.input(|i| i.key_pressed(egui::Key::Escape)) {
-                        LOGGER.trace("escape pressed!");
-                        // close the window
-                        glfw_backend.window.set_should_close(true);
-                    };
+                    if pressed(KeyAction::Close, typing) {
+                        if self.searchholder.results.focused() {
+                            LOGGER.trace("close pressed while focused on a group!");
+                            // leave the focused group before closing anything
+                            self.searchholder.results.toggle_focus();
+                        } else {
+                            LOGGER.trace("close pressed!");
+                            // close the window
+                            glfw_backend.window.set_should_close(true);
+                        }
+                    }
 
                     egui_context.used_size().x
                 })
                 .map(|x| x.response.rect.height())
                 .unwrap_or(0.0);
 
-            if !self.searchholder.results.is_empty() {
-                let mut set_cursor_later = None;
+            if let Some(search) = &self.history_search {
+                let (pivot, pos) = layout.opposite(barheight);
+                egui::Window::new("History Search")
+                    .title_bar(false)
+                    .resizable(false)
+                    .pivot(pivot)
+                    .fixed_pos(pos)
+                    .min_size(egui::Vec2::new(400., 0.))
+                    .show(egui_context, |ui| {
+                        ui.add(egui::Label::new(format!("reverse search: {}", search.needle)).wrap(false));
+                        ui.separator();
+                        match &search.found {
+                            Some((_, query)) => ui.add(egui::Label::new(RichText::new(query).color(self.config_lock.get().theme.selection())).wrap(false)),
+                            None => ui.add(egui::Label::new(RichText::new("no match").weak()).wrap(false)),
+                        };
+                    });
+            }
+
+            let statuses = if self.config_lock.get().show_plugin_status {
+                self.searchholder.statuses(self.config_lock.get(), &self.input)
+            } else {
+                Vec::new()
+            };
+            if statuses.iter().any(|(_, status)| matches!(status, StatusView::Debouncing(_))) {
+                // keep the countdown moving
+                self.force_redraw_now = true;
+            }
 
-                egui::Window::new("Results")
+            if !self.searchholder.results.is_empty() || !statuses.is_empty() {
+                let mut set_cursor_later = None;
+                let mut mark_later = None;
+                let mut toggle_collapsed_later = None;
+                let mut scrolled_to_later = None;
+                let mut focus_offset_later = None;
+                let mut show_more_later = None;
+                let mut hovered_later = None;
+                let mut execute_later = None;
+                let mut secondary_later = None;
+                let results_max_height = layout.results_max_height(barheight);
+                let (results_pivot, results_pos) = layout.results(barheight);
+                let (opposite_pivot, opposite_pos) = layout.opposite(barheight);
+                let ctrl_held = egui_context.input(|i| i.modifiers.ctrl);
+                let mut open_menu_later = false;
+                let mut cursor_rect = None;
+                let preview_placement = self.config_lock.get().preview_placement;
+                let show_icons = self.config_lock.get().show_icons;
+                let fonts = self.config_lock.get().fonts.clone();
+                let icon_size = fonts.title_size;
+
+                let results = egui::Window::new("Results")
                     .title_bar(false)
                     // .fixed_pos(Pos2::new(midwindowx as f32, midwindowy as f32))
                     // .fixed_size(Vec2::new(400., 60.))
                     .resizable(false)
                     // .anchor(egui::Align2::CENTER_TOP, egui::Vec2::new(0., midwindowy as f32 + 30.))
-                    .pivot(egui::Align2::CENTER_TOP)
-                    .fixed_pos(egui::Pos2::new(
-                        midwindowx as f32,
-                        midwindowy as f32 + (barheight / 2.0) + self.config_lock.get().gap_between_search_bar_and_results,
-                    ))
+                    .pivot(results_pivot)
+                    .fixed_pos(results_pos);
+                // a fixed width bar gets results of the same width so their edges line up
+                let results = match layout.width() {
+                    Some(width) => results.min_width(width).max_width(width),
+                    None => results,
+                };
+                let results_rect = results
                     .show(egui_context, |ui| {
                         // if !self.joinhandles.is_empty() {
                         //     ui.spinner();
@@ -498,92 +1071,381 @@ impl egui_overlay::EguiOverlay for App<'_> {
                         //     };
                         // }
 
-                        for e in self
-                            .searchholder
-                            .results
-                            .iter_nice(self.scrolling, self.config_lock.get().entries_around_cursor, self.config_lock.get().group_entries_while_unselected)
-                        {
-                            match e {
-                                NiceIter::NewSource(source) => {
-                                    ui.horizontal(|ui| {
-                                        ui.add(egui::Label::new(source.pretty_name.clone()).wrap(false));
-                                        ui.separator();
-                                        ui.add(egui::Label::new(format!("{} Results", source.num_results)).wrap(false));
-                                    });
+                        let mut show_entry = |ui: &mut egui::Ui, e: NiceIter<'_>| match e {
+                            NiceIter::NewSource {
+                                metadata,
+                                num_results,
+                                finished,
+                                collapsed,
+                            } => {
+                                let header = ui.horizontal(|ui| {
+                                    ui.add(
+                                        egui::Label::new(
+                                            RichText::new(if collapsed { "▶" } else { "▼" })
+                                                .size(fonts.group_header_size)
+                                                .color(self.config_lock.get().theme.group_header()),
+                                        )
+                                        .wrap(false),
+                                    );
+                                    if show_icons {
+                                        if let Some(icon) = &metadata.icon {
+                                            self.icons.show(ui, icon, icon_size);
+                                        }
+                                    }
+                                    ui.add(egui::Label::new(sized(&metadata.pretty_name, fonts.group_header_size)).wrap(false));
                                     ui.separator();
+                                    ui.add(
+                                        egui::Label::new(
+                                            RichText::new(format!("{} Results", num_results))
+                                                .size(fonts.group_header_size)
+                                                .color(self.config_lock.get().theme.group_header()),
+                                        )
+                                        .wrap(false),
+                                    );
+                                    if !finished {
+                                        ui.spinner();
+                                    }
+                                });
+                                if header
+                                    .response
+                                    .interact(egui::Sense::click())
+                                    .on_hover_text(format!(
+                                        "Click to collapse or expand, {} and {} do the same while scrolling",
+                                        keymap.describe(KeyAction::Collapse),
+                                        keymap.describe(KeyAction::Expand)
+                                    ))
+                                    .clicked()
+                                {
+                                    toggle_collapsed_later = Some(metadata.raw_name.clone());
+                                }
+                                ui.separator();
+                            }
+                            NiceIter::More { metadata, remaining } => {
+                                if ui
+                                    .add(egui::Label::new(RichText::new(format!("show {} more", remaining)).weak()).wrap(false).sense(egui::Sense::click()))
+                                    .on_hover_text(format!("Click or press {} while scrolling to show more results from this plugin", keymap.describe(KeyAction::ShowMore)))
+                                    .clicked()
+                                {
+                                    show_more_later = Some(metadata.raw_name.clone());
+                                }
+                            }
+                            NiceIter::Result {
+                                result,
+                                also_from,
+                                marked,
+                                cursor_on,
+                                index,
+                                source,
+                            } => {
+                                let (short_title, title_truncated) = {
+                                    let mut title = result.title().to_string();
+                                    let mut truncated = false;
+                                    if title.len() > self.config_lock.get().truncate_title_length {
+                                        title.truncate(self.config_lock.get().truncate_title_length - 3);
+                                        title.push_str("...");
+                                        truncated = true;
+                                    }
+                                    (title, truncated)
+                                };
+
+                                let (short_context, context_truncated) = {
+                                    let mut context = result.context().to_string();
+                                    let mut truncated = false;
+                                    if context.len() > self.config_lock.get().truncate_context_length {
+                                        context.truncate(self.config_lock.get().truncate_context_length - 3);
+                                        context.push_str("...");
+                                        truncated = true;
+                                    }
+                                    (context, truncated)
+                                };
+
+                                let icon = if show_icons { self.icons.for_result(ui.ctx(), result) } else { None };
+                                let also_from = if also_from.is_empty() {
+                                    None
+                                } else {
+                                    Some(format!("also from: {}", also_from.iter().map(|d| d.metadata.raw_name.as_str()).collect::<Vec<_>>().join(", ")))
+                                };
+
+                                // every row is drawn and handled the same way whether or not the results are being scrolled through
+                                let background = ui.painter().add(egui::Shape::Noop);
+                                let row = ui.vertical(|ui| {
+                                    let title_rect = ui
+                                        .horizontal(|ui| {
+                                            if let Some(icon) = &icon {
+                                                self.icons.show(ui, icon, icon_size);
+                                            }
+                                            if marked {
+                                                ui.add(egui::Label::new(RichText::new("✔").color(self.config_lock.get().theme.marked())).wrap(false));
+                                            }
+                                            let title = RichText::new(short_title).size(fonts.title_size);
+                                            let title = if cursor_on { title.color(self.config_lock.get().theme.selection()) } else { title };
+                                            ui.add(egui::Label::new(title).wrap(false));
+                                            if let Some(source) = &source {
+                                                ui.add(egui::Label::new(RichText::new(&source.raw_name).small().weak()).wrap(false));
+                                            }
+                                            if let Some(also_from) = &also_from {
+                                                ui.add(egui::Label::new(RichText::new(also_from).small().weak()).wrap(false))
+                                                    .on_hover_text("The same result was returned by other plugins, they can be picked from the actions menu");
+                                            }
+                                        })
+                                        .response
+                                        .rect;
+                                    if cursor_on && !result.context().is_empty() {
+                                        ui.add(egui::Label::new(RichText::new(short_context).size(fonts.context_size).color(self.config_lock.get().theme.context())).wrap(false));
+                                    }
+                                    title_rect
+                                });
+                                let title_rect = row.inner;
+                                let response = row.response.interact(egui::Sense::click());
+                                if response.hovered() {
+                                    hovered_later = Some(index);
+                                    let visuals = &ui.visuals().widgets.hovered;
+                                    ui.painter()
+                                        .set(background, egui::Shape::rect_filled(response.rect.expand(2.0), visuals.rounding, visuals.weak_bg_fill));
+                                }
+                                if response.clicked() {
+                                    if ctrl_held {
+                                        mark_later = Some(index);
+                                    }
+                                    set_cursor_later = Some(index);
+                                }
+                                if response.double_clicked() && !ctrl_held {
+                                    execute_later = Some(index);
+                                }
+                                if response.middle_clicked() {
+                                    secondary_later = Some(index);
+                                }
+                                if response.secondary_clicked() {
+                                    set_cursor_later = Some(index);
+                                    open_menu_later = true;
                                 }
-                                NiceIter::Result { result, cursor_on, index } => {
-                                    let (short_title, title_truncated) = {
-                                        let mut title = result.title().to_string();
-                                        let mut truncated = false;
-                                        if title.len() > self.config_lock.get().truncate_title_length {
-                                            title.truncate(self.config_lock.get().truncate_title_length - 3);
-                                            title.push_str("...");
-                                            truncated = true;
-                                        }
-                                        (title, truncated)
-                                    };
 
-                                    let (short_context, context_truncated) = {
-                                        let mut context = result.context().to_string();
-                                        let mut truncated = false;
-                                        if context.len() > self.config_lock.get().truncate_context_length {
-                                            context.truncate(self.config_lock.get().truncate_context_length - 3);
-                                            context.push_str("...");
-                                            truncated = true;
-                                        }
-                                        (context, truncated)
-                                    };
+                                if cursor_on {
+                                    cursor_rect = Some(egui::Rect::from_min_max(title_rect.min, egui::Pos2::new(ui.max_rect().right(), title_rect.max.y)));
+                                    if (context_truncated || title_truncated) && preview_placement == PreviewPlacement::Off {
+                                        // like the current result window but on the other side of the search bar
+                                        egui::Window::new("Full Result")
+                                            .title_bar(false)
+                                            // .fixed_pos(Pos2::new(midwindowx as f32, midwindowy as f32))
+                                            // .fixed_size(Vec2::new(400., 60.))
+                                            .resizable(false)
+                                            .pivot(opposite_pivot)
+                                            .fixed_pos(opposite_pos)
+                                            .min_size(egui::Vec2::new(400., 60.))
+                                            // .anchor(egui::Align2::CENTER_TOP, egui::Vec2::new(0., midwindowy as f32 - 30.))
+                                            .show(egui_context, |ui| {
+                                                if title_truncated {
+                                                    ui.add(egui::Label::new(RichText::new(result.title()).heading().color(self.config_lock.get().theme.selection())).wrap(true));
+                                                }
+                                                if title_truncated && context_truncated {
+                                                    ui.separator();
+                                                }
+                                                if context_truncated {
+                                                    ui.add(egui::Label::new(RichText::new(result.context()).color(self.config_lock.get().theme.context())).wrap(true));
+                                                }
+                                            });
+                                    }
+                                }
+                            }
+                        };
 
-                                    if cursor_on {
-                                        ui.add(egui::Label::new(RichText::new(short_title).color(egui::Color32::LIGHT_BLUE)).wrap(false));
-                                        if !result.context().is_empty() {
-                                            ui.add(egui::Label::new(RichText::new(short_context).color(egui::Color32::from_rgb(0, 128, 255))).wrap(false));
-                                        }
-                                        if context_truncated || title_truncated {
-                                            // like the current result window but above the search bar
-                                            egui::Window::new("Full Result")
-                                                .title_bar(false)
-                                                // .fixed_pos(Pos2::new(midwindowx as f32, midwindowy as f32))
-                                                // .fixed_size(Vec2::new(400., 60.))
-                                                .resizable(false)
-                                                .pivot(egui::Align2::CENTER_BOTTOM)
-                                                .fixed_pos(egui::Pos2::new(
-                                                    midwindowx as f32,
-                                                    midwindowy as f32 - (barheight / 2.0) - self.config_lock.get().gap_between_search_bar_and_results,
-                                                ))
-                                                .min_size(egui::Vec2::new(400., 60.))
-                                                // .anchor(egui::Align2::CENTER_TOP, egui::Vec2::new(0., midwindowy as f32 - 30.))
-                                                .show(egui_context, |ui| {
-                                                    if title_truncated {
-                                                        ui.add(egui::Label::new(RichText::new(result.title()).heading().color(egui::Color32::LIGHT_BLUE)).wrap(true));
-                                                    }
-                                                    if title_truncated && context_truncated {
-                                                        ui.separator();
-                                                    }
-                                                    if context_truncated {
-                                                        ui.add(egui::Label::new(RichText::new(result.context()).color(egui::Color32::from_rgb(0, 128, 255))).wrap(true));
-                                                    }
-                                                });
-                                        }
-                                    } else if self.scrolling {
-                                        ui.add(egui::Label::new(short_title).wrap(false));
+                        // a focused group can hold every result a plugin returned, so only the rows in view are laid out
+                        match self.searchholder.results.focus_header() {
+                            Some((header, rows)) if self.scrolling => {
+                                show_entry(ui, header);
+                                let row_height = ui.fonts(|f| f.row_height(&egui::FontId::proportional(fonts.title_size)) + f.row_height(&egui::FontId::proportional(fonts.context_size)))
+                                    + ui.spacing().item_spacing.y;
+                                let mut area = egui::ScrollArea::vertical().max_height(results_max_height).auto_shrink([false, true]);
+                                // keep the cursor in view when it moves, otherwise leave the scrollbar where the user put it
+                                let cursor = self.searchholder.results.cursor();
+                                if let Some(row) = self.searchholder.results.focus_cursor().filter(|_| self.scrolled_to != Some(cursor)) {
+                                    let top = row as f32 * (row_height + ui.spacing().item_spacing.y);
+                                    let offset = if top < self.focus_offset {
+                                        top
+                                    } else if top + row_height > self.focus_offset + results_max_height {
+                                        top + row_height - results_max_height
                                     } else {
-                                        // i dont like inlining if statements if there are side effects
-                                        if ui.add_enabled(true, egui::Button::new(short_title).wrap(false).frame(false)).clicked() {
-                                            set_cursor_later = Some(index);
-                                        }
+                                        self.focus_offset
                                     };
+                                    area = area.vertical_scroll_offset(offset);
+                                    scrolled_to_later = Some(cursor);
                                 }
+                                let output = area.show_rows(ui, row_height, rows, |ui, rows| {
+                                    for e in self.searchholder.results.iter_focus(rows) {
+                                        ui.vertical(|ui| {
+                                            ui.set_min_height(row_height);
+                                            show_entry(ui, e);
+                                        });
+                                    }
+                                });
+                                focus_offset_later = Some(output.state.offset.y);
+                            }
+                            _ => {
+                                // show_rows needs every row to be the same height, so every row gets room for a title and a context line like in focus mode,
+                                // and headers room for their separator, otherwise rows below the cursor or a header drift out of place while scrolling
+                                let row_height = ui.fonts(|f| {
+                                    let result = f.row_height(&egui::FontId::proportional(fonts.title_size)) + f.row_height(&egui::FontId::proportional(fonts.context_size));
+                                    let header = f.row_height(&egui::FontId::proportional(fonts.group_header_size)) + SEPARATOR_SPACING;
+                                    result.max(header)
+                                }) + ui.spacing().item_spacing.y;
+                                let around = self.config_lock.get().entries_around_cursor;
+                                let unselected = self.config_lock.get().group_entries_while_unselected;
+                                let rows = self.searchholder.results.nice_rows(self.scrolling, around, unselected);
+                                egui::ScrollArea::vertical().max_height(results_max_height).show_rows(ui, row_height, rows, |ui, rows| {
+                                    for e in self.searchholder.results.iter_nice(self.scrolling, around, unselected, rows) {
+                                        ui.vertical(|ui| {
+                                            ui.set_min_height(row_height);
+                                            show_entry(ui, e);
+                                        });
+                                    }
+                                });
+                            }
+                        }
+
+                        if !statuses.is_empty() {
+                            if !self.searchholder.results.is_empty() {
+                                ui.separator();
+                            }
+                            for (metadata, status) in statuses.iter() {
+                                show_status(ui, metadata, status, &self.config_lock.get().theme);
                             }
                         }
 
                         egui_context.used_size().x
-                    });
+                    })
+                    .map(|x| x.response);
+                self.pointer_over_results = results_rect.as_ref().map(|r| r.contains_pointer()).unwrap_or(false);
+                let results_rect = results_rect.map(|r| r.rect);
+                if hovered_later != self.hovered {
+                    // the highlight follows the mouse without waiting for the next timed repaint
+                    self.hovered = hovered_later;
+                    self.force_redraw_now = true;
+                }
                 if let Some(index) = set_cursor_later {
                     self.searchholder.results.raw_set_cursor(index);
-                    self.scrolling = true;
+                    self.enter_results_with_mouse(egui_context);
+                }
+                if let Some(index) = mark_later {
+                    self.searchholder.results.toggle_mark(index);
+                }
+                if let Some(name) = toggle_collapsed_later {
+                    let collapsed = self.searchholder.results.toggle_collapsed(&name);
+                    self.remember_collapsed(&name, collapsed);
+                }
+                if scrolled_to_later.is_some() {
+                    self.scrolled_to = scrolled_to_later;
+                }
+                if let Some(offset) = focus_offset_later {
+                    self.focus_offset = offset;
+                }
+                if let Some(name) = show_more_later {
+                    self.searchholder.results.show_more(&name);
+                }
+                if let Some(index) = execute_later {
+                    self.searchholder.results.raw_set_cursor(index);
+                    if let Some((result, plugin_id)) = self.searchholder.results.get_from_cursor().map(|(result, id)| (result.clone(), id)) {
+                        self.execute(&result, &plugin_id);
+                        self.remember_query();
+                    }
+                    glfw_backend.window.set_should_close(true);
+                }
+                if let Some(index) = secondary_later {
+                    self.searchholder.results.raw_set_cursor(index);
+                    self.enter_results_with_mouse(egui_context);
+                    if let Some(id) = self.searchholder.results.get_from_cursor().map(|(result, _)| actions::secondary(result)) {
+                        self.run_action(id, egui_context);
+                        glfw_backend.window.set_should_close(true);
+                    }
+                }
+                self.highlighted = if self.scrolling {
+                    self.searchholder.results.get_from_cursor().map(|(result, id)| (result.clone(), id))
+                } else {
+                    None
+                };
+                self.cursor_rect = cursor_rect;
+                if open_menu_later {
+                    self.open_action_menu();
                 }
+
+                if preview_placement != PreviewPlacement::Off && self.scrolling {
+                    self.update_preview();
+                    if let (Some(content), Some(results_rect)) = (self.preview.as_ref().and_then(|p| p.content.as_ref()), results_rect) {
+                        let gap = self.config_lock.get().gap_between_search_bar_and_results;
+                        let (pivot, pos) = match preview_placement {
+                            PreviewPlacement::Side => (egui::Align2::LEFT_TOP, results_rect.right_top() + egui::Vec2::new(gap, 0.0)),
+                            _ => (opposite_pivot, opposite_pos),
+                        };
+                        egui::Window::new("Preview")
+                            .title_bar(false)
+                            .resizable(false)
+                            .pivot(pivot)
+                            .fixed_pos(pos)
+                            .min_size(egui::Vec2::new(400., 60.))
+                            .max_width(600.)
+                            .show(egui_context, |ui| {
+                                for (i, block) in content.iter().enumerate() {
+                                    if i != 0 {
+                                        ui.separator();
+                                    }
+                                    match block {
+                                        PreviewBlock::Text(text) => {
+                                            ui.add(egui::Label::new(text.as_str()).wrap(true));
+                                        }
+                                        PreviewBlock::Markdown(text) => show_markdown(ui, text),
+                                        PreviewBlock::Table(rows) => {
+                                            egui::Grid::new("preview_table").striped(true).show(ui, |ui| {
+                                                for (key, value) in rows {
+                                                    ui.label(RichText::new(key).strong());
+                                                    ui.label(value);
+                                                    ui.end_row();
+                                                }
+                                            });
+                                        }
+                                        PreviewBlock::Image(path) => {
+                                            self.preview_images.show(ui, &ResultIcon::Path(path.clone()), 256.0);
+                                        }
+                                    }
+                                }
+                            });
+                    }
+                }
+            } else {
+                self.cursor_rect = None;
+                self.highlighted = None;
+                self.hovered = None;
+                self.pointer_over_results = false;
+            }
+
+            let mut run_later = None;
+            match (&mut self.action_menu, self.cursor_rect) {
+                (Some(menu), Some(rect)) => {
+                    egui::Window::new("Actions")
+                        .title_bar(false)
+                        .resizable(false)
+                        .pivot(egui::Align2::LEFT_TOP)
+                        .fixed_pos(rect.right_top() + egui::Vec2::new(self.config_lock.get().gap_between_search_bar_and_results, 0.0))
+                        .show(egui_context, |ui| {
+                            for (i, action) in menu.actions.iter().enumerate() {
+                                let text = match action.shortcut {
+                                    Some(key) => format!("{} ({})", action.name, key.name()),
+                                    None => action.name.clone(),
+                                };
+                                if ui.selectable_label(menu.selected == i, text).clicked() {
+                                    run_later = Some(action.id.clone());
+                                }
+                            }
+                        });
+                }
+                (Some(_), None) => {
+                    // the row the menu belonged to is gone
+                    self.action_menu = None;
+                }
+                _ => {}
+            }
+            if let Some(id) = run_later {
+                self.action_menu = None;
+                self.run_action(&id, egui_context);
+                glfw_backend.window.set_should_close(true);
             }
         }
 
@@ -610,33 +1472,83 @@ impl egui_overlay::EguiOverlay for App<'_> {
 
 pub struct SearchHolder {
     loadresults: PluginLoadResult,
-    joinhandles: Vec<std::thread::JoinHandle<(Vec<SearchResult>, SearchMetadata)>>,
-    oldhandles: Vec<std::thread::JoinHandle<(Vec<SearchResult>, SearchMetadata)>>,
+    sender: crossbeam::channel::Sender<SearchMessage>,
+    receiver: crossbeam::channel::Receiver<SearchMessage>,
+    // bumped every time the input changes, so results from stale searches can be dropped
+    generation: u64,
     last_changed: Option<std::time::Instant>,
     dispatched_searches: HashSet<String>,
+    // the query the current generation was dispatched with
+    query: SearchQuery,
+    // plugins showing a stale cached group, the fresh results replace it
+    refreshing: HashSet<&'static str>,
+    // every plugin dispatched for the current generation
+    status: HashMap<&'static str, PluginStatus>,
+    // the fallback group is only added once per generation
+    fallback_shown: bool,
     results: ResultHolder,
 }
 
 impl SearchHolder {
     pub fn new(loadresults: PluginLoadResult) -> Self {
+        let (sender, receiver) = crossbeam::channel::unbounded();
         Self {
             loadresults,
-            joinhandles: Vec::default(),
-            oldhandles: Vec::default(),
+            sender,
+            receiver,
+            generation: 0,
             last_changed: Option::default(),
             dispatched_searches: HashSet::default(),
+            query: SearchQuery::default(),
+            refreshing: HashSet::default(),
+            status: HashMap::default(),
+            fallback_shown: false,
             results: ResultHolder::default(),
         }
     }
     pub fn input_changed(&mut self) {
+        if let Some(changed) = self.last_changed {
+            crate::PLUGIN_METRICS.record_keystroke(changed.elapsed());
+        }
         self.last_changed = Some(std::time::Instant::now());
         self.dispatched_searches.clear();
-        self.oldhandles.append(&mut self.joinhandles);
+        self.generation += 1;
+        self.refreshing.clear();
+        self.status.clear();
+        self.fallback_shown = false;
         self.results.clear();
     }
+
+    // plugins that should be searched for the input but have nothing on screen, and why
+    pub fn statuses(&self, config: &crate::config::Config, input: &str) -> Vec<(SearchMetadata, StatusView)> {
+        let query = SearchQuery::parse(input);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let timeout = std::time::Duration::from_millis(config.search_timeout as u64);
+        let elapsed = self.last_changed.map(|changed| changed.elapsed()).unwrap_or_default();
+        self.loadresults
+            .plugins
+            .iter()
+            .filter(|plugin| query.searches_plugin(plugin.name) && !self.results.has_group(plugin.name))
+            .filter_map(|plugin| {
+                let view = match self.status.get(plugin.name) {
+                    Some(status) => status.view(timeout)?,
+                    None => {
+                        let wait = std::time::Duration::from_millis(config.total_search_delay as u64 + config.plugin_delay(plugin.name, &crate::PLUGIN_METRICS) as u64);
+                        StatusView::Debouncing(wait.saturating_sub(elapsed))
+                    }
+                };
+                Some((plugin.metadata(), view))
+            })
+            .collect()
+    }
     pub fn dispatch(&mut self, config: &ConfigLock<'_>, input: &str) {
         let config = config.get();
-        self.oldhandles.retain(|handle| !handle.is_finished());
+        crate::SEARCH_POOL.resize(config.worker_threads);
+        crate::PLUGIN_METRICS.check_deadlines();
+        let query = SearchQuery::parse(input);
+        self.results.set_ranking(config.display_mode == DisplayMode::Ranked, &query.text());
 
         let time_since_last_change = self
             .last_changed
@@ -644,34 +1556,124 @@ impl SearchHolder {
             .unwrap_or(0)
             .saturating_sub(config.total_search_delay as u128);
 
-        if !input.is_empty() {
+        if !query.is_empty() {
             for plugin in self.loadresults.plugins.iter() {
+                // plugins left out with @ or -@ are never called
+                if !query.searches_plugin(plugin.name) {
+                    continue;
+                }
                 // if it has been long enough since the last change, and the search has not been dispatched, then dispatch the search
-                if (config.get_plugin(plugin.name).map(|p| p.delay).unwrap_or(100) as u128) < time_since_last_change && !self.dispatched_searches.contains(plugin.name) {
-                    LOGGER.trace(&format!("dispatching search for {} after {}ms", plugin.name, time_since_last_change));
-                    self.joinhandles.push(plugin.search_delayed(input));
+                if (config.plugin_delay(plugin.name, &crate::PLUGIN_METRICS) as u128) < time_since_last_change && !self.dispatched_searches.contains(plugin.name) {
                     self.dispatched_searches.insert(plugin.name.to_string());
+                    self.query = query.clone();
+
+                    let plugin_config = config.get_plugin(plugin.name);
+                    if config.cache_size > 0 && plugin_config.map(|p| p.cache_enabled).unwrap_or(true) {
+                        let ttl = std::time::Duration::from_secs(plugin_config.map(|p| p.cache_ttl).unwrap_or(60) as u64);
+                        match crate::RESULT_CACHE.get(plugin.name, &query.text(), ttl) {
+                            CacheLookup::Fresh(cached) => {
+                                LOGGER.trace(&format!("using cached results for {}", plugin.name));
+                                let cached = cached.into_iter().filter(|r| query.matches_phrases(r)).collect::<Vec<_>>();
+                                self.status.insert(plugin.name, PluginStatus::Finished(cached.len()));
+                                if !cached.is_empty() {
+                                    self.results.add_results(cached, plugin.metadata());
+                                    apply_frecency(&mut self.results, config, plugin.name, &query.text());
+                                    self.results.finish_group(plugin.name);
+                                }
+                                continue;
+                            }
+                            CacheLookup::Stale(cached) => {
+                                LOGGER.trace(&format!("using stale cached results for {} while refreshing", plugin.name));
+                                let cached = cached.into_iter().filter(|r| query.matches_phrases(r)).collect::<Vec<_>>();
+                                if !cached.is_empty() {
+                                    self.results.add_results(cached, plugin.metadata());
+                                    apply_frecency(&mut self.results, config, plugin.name, &query.text());
+                                    self.refreshing.insert(plugin.name);
+                                }
+                            }
+                            CacheLookup::Miss => {}
+                        }
+                    }
+
+                    LOGGER.trace(&format!("dispatching search for {} after {}ms", plugin.name, time_since_last_change));
+                    self.status.insert(plugin.name, PluginStatus::Pending(std::time::Instant::now()));
+                    plugin.search_pooled(
+                        &query,
+                        std::time::Duration::from_millis(config.search_timeout as u64),
+                        SearchSink::new(plugin.name, self.generation, self.sender.clone()),
+                    );
                 }
             }
         }
 
-        let mut newhandles = vec![];
-
-        for handle in self.joinhandles.drain(..) {
-            if handle.is_finished() {
-                if let Ok((r, m)) = handle.join() {
-                    LOGGER.trace(&format!("search thread finished for {} with {} results", m.raw_name, r.len()));
-                    if !r.is_empty() {
-                        self.results.add_results(r, m);
+        for message in self.receiver.try_iter() {
+            if message.generation != self.generation {
+                LOGGER.trace(&format!("dropping stale search message for {}", message.plugin));
+                continue;
+            }
+            match message.event {
+                SearchEvent::Results(r) => {
+                    LOGGER.trace(&format!("search finished for {} with {} results", message.plugin, r.len()));
+                    if config.cache_size > 0 && config.get_plugin(message.plugin).map(|p| p.cache_enabled).unwrap_or(true) {
+                        crate::RESULT_CACHE.insert(message.plugin, &self.query.text(), r.clone(), config.cache_size);
                     }
-                } else {
-                    LOGGER.error("search thread failed");
+                    let r = r.into_iter().filter(|r| self.query.matches_phrases(r)).collect::<Vec<_>>();
+                    self.status.insert(message.plugin, PluginStatus::Finished(r.len()));
+                    if let Some(plugin) = self.loadresults.plugins.iter().find(|p| p.name == message.plugin) {
+                        if self.refreshing.remove(message.plugin) {
+                            // an empty refresh removes the stale group
+                            self.results.replace_results(r, plugin.metadata());
+                        } else if !r.is_empty() {
+                            self.results.add_results(r, plugin.metadata());
+                        }
+                        apply_frecency(&mut self.results, config, message.plugin, &self.query.text());
+                    }
+                    self.results.finish_group(message.plugin);
+                }
+                SearchEvent::Failed(e) => {
+                    LOGGER.error(&format!("search thread failed for {}: {}", message.plugin, e));
+                    self.status.insert(message.plugin, PluginStatus::Failed(e));
+                    self.refreshing.remove(message.plugin);
+                    self.results.finish_group(message.plugin);
                 }
-            } else {
-                newhandles.push(handle);
             }
         }
 
-        self.joinhandles = newhandles;
+        // every plugin has answered and none of them had anything
+        let all_answered = self
+            .loadresults
+            .plugins
+            .iter()
+            .filter(|plugin| query.searches_plugin(plugin.name))
+            .all(|plugin| matches!(self.status.get(plugin.name), Some(PluginStatus::Finished(_)) | Some(PluginStatus::Failed(_))));
+        if !self.fallback_shown && !query.is_empty() && all_answered && self.results.is_empty() {
+            self.fallback_shown = true;
+            let results = fallback::results(&config.fallbacks, &query.text());
+            if !results.is_empty() {
+                let metadata = fallback::metadata();
+                let name = metadata.raw_name.clone();
+                self.results.add_results(results, metadata);
+                self.results.finish_group(&name);
+            }
+        }
+    }
+}
+
+// moves the results the user picks most often to the top of their group, called once when a plugin's results arrive
+fn apply_frecency(results: &mut ResultHolder, config: &crate::config::Config, plugin: &str, query: &str) {
+    if config.frecency_enabled {
+        let scorer = crate::USAGE_STORE.scorer(query);
+        if scorer.knows(plugin) {
+            results.reorder_group(plugin, |result| scorer.score(plugin, result));
+        }
+    }
+}
+
+// plugin names come with their own colors, only the size is changed
+fn sized(job: &egui::text::LayoutJob, size: f32) -> egui::text::LayoutJob {
+    let mut job = job.clone();
+    for section in job.sections.iter_mut() {
+        section.format.font_id.size = size;
     }
+    job
 }
@@ -0,0 +1,92 @@
+// scores how well the query matches the text as a case insensitive subsequence, higher is better, None if not every query character is present in order
+pub fn fuzzy_score(query: &str, text: &str) -> Option<f32> {
+    let query = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+    if query.is_empty() {
+        return Some(0.0);
+    }
+    let lower = text.to_lowercase();
+    let text_chars = lower.chars().collect::<Vec<_>>();
+
+    let mut score = 0.0;
+    let mut query_index = 0;
+    let mut last_match: Option<usize> = None;
+
+    for (i, c) in text_chars.iter().enumerate() {
+        if query_index >= query.len() {
+            break;
+        }
+        if *c != query[query_index] {
+            continue;
+        }
+        score += 1.0;
+        // consecutive characters are worth more than scattered ones
+        if last_match.map(|last| last + 1 == i).unwrap_or(false) {
+            score += 2.0;
+        }
+        // so are matches at the start of a word
+        if i == 0 || !text_chars[i - 1].is_alphanumeric() {
+            score += 3.0;
+        }
+        last_match = Some(i);
+        query_index += 1;
+    }
+
+    if query_index < query.len() {
+        return None;
+    }
+
+    let needle = query.iter().collect::<String>();
+    if lower.starts_with(&needle) {
+        score += 10.0;
+    } else if lower.contains(&needle) {
+        score += 5.0;
+    }
+    // prefer shorter texts when everything else is equal
+    Some(score - text_chars.len() as f32 * 0.01)
+}
+
+// fuzzy_score scaled to 0..=1 by the best score a query of its length could get, so short and long queries rank on the same scale
+pub fn match_quality(query: &str, text: &str) -> Option<f32> {
+    let len = query.chars().filter(|c| !c.is_whitespace()).count();
+    if len == 0 {
+        return Some(0.0);
+    }
+    // every character consecutive and at a word start, plus the prefix bonus
+    let best = len as f32 * 6.0 + 8.0;
+    fuzzy_score(query, text).map(|score| (score / best).clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_character_has_to_match_in_order() {
+        assert!(fuzzy_score("qs", "quick search").is_some());
+        assert!(fuzzy_score("sq", "quick search").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0.0));
+    }
+
+    #[test]
+    fn prefix_beats_contains_beats_scattered() {
+        let prefix = fuzzy_score("note", "notes.txt").unwrap();
+        let contains = fuzzy_score("note", "my notes.txt").unwrap();
+        let scattered = fuzzy_score("note", "no time").unwrap();
+        assert!(prefix > contains);
+        assert!(contains > scattered);
+    }
+
+    #[test]
+    fn shorter_text_wins_a_tie() {
+        assert!(fuzzy_score("ab", "ab") > fuzzy_score("ab", "ab cd"));
+    }
+
+    #[test]
+    fn quality_stays_between_zero_and_one() {
+        for (query, text) in [("a", "a"), ("note", "notes.txt"), ("qs", "quick search"), ("a b c", "a b c")] {
+            let quality = match_quality(query, text).unwrap();
+            assert!((0.0..=1.0).contains(&quality), "{} in {} scored {}", query, text, quality);
+        }
+        assert!(match_quality("zz", "notes").is_none());
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+
+use egui::RichText;
+
+use crate::search_instance::{SearchMetadata, Theme};
+
+// what a plugin is doing for the current query
+pub enum PluginStatus {
+    Pending(Instant),
+    Finished(usize),
+    Failed(String),
+}
+
+// what is shown for a plugin that has no results on screen
+pub enum StatusView {
+    // still waiting for the delay before the search is dispatched
+    Debouncing(Duration),
+    Pending,
+    // running for longer than the search timeout, the pool can't cancel it so it may still answer
+    TimedOut,
+    Empty,
+    Failed(String),
+}
+
+impl PluginStatus {
+    pub fn view(&self, timeout: Duration) -> Option<StatusView> {
+        match self {
+            PluginStatus::Pending(since) if since.elapsed() > timeout => Some(StatusView::TimedOut),
+            PluginStatus::Pending(_) => Some(StatusView::Pending),
+            PluginStatus::Finished(0) => Some(StatusView::Empty),
+            PluginStatus::Finished(_) => None,
+            PluginStatus::Failed(e) => Some(StatusView::Failed(e.clone())),
+        }
+    }
+}
+
+pub fn show_status(ui: &mut egui::Ui, metadata: &SearchMetadata, status: &StatusView, theme: &Theme) {
+    ui.horizontal(|ui| {
+        ui.add(egui::Label::new(metadata.pretty_name.clone()).wrap(false));
+        ui.separator();
+        match status {
+            StatusView::Debouncing(remaining) => {
+                ui.add(egui::Label::new(RichText::new(format!("searching in {:.2}s", remaining.as_secs_f32())).weak()).wrap(false));
+            }
+            StatusView::Pending => {
+                ui.spinner();
+            }
+            StatusView::TimedOut => {
+                ui.spinner();
+                ui.add(egui::Label::new(RichText::new("timed out").color(theme.warning())).wrap(false));
+            }
+            StatusView::Empty => {
+                ui.add(egui::Label::new(RichText::new("no results").weak()).wrap(false));
+            }
+            StatusView::Failed(e) => {
+                // only the host side of the call can fail like this, a panic inside the plugin aborts the whole process
+                ui.add(egui::Label::new(RichText::new(format!("failed handling results: {}", e)).color(theme.error())).wrap(false))
+                    .on_hover_text("The search panicked on the QuickSearch side, e.g. while converting the plugin's results. A panic inside the plugin itself can't be caught and closes QuickSearch.");
+            }
+        }
+    });
+}
@@ -0,0 +1,130 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use quick_search_lib::SearchResult;
+
+// results from previous searches keyed on (plugin, query), shared between every search window for the lifetime of the process
+#[derive(Default)]
+pub struct ResultCache {
+    state: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<(String, String), CacheEntry>,
+    // incremented on every access, the entry with the lowest last_used is evicted first
+    tick: u64,
+}
+
+struct CacheEntry {
+    results: Vec<SearchResult>,
+    stored: std::time::Instant,
+    last_used: u64,
+}
+
+pub enum CacheLookup {
+    Fresh(Vec<SearchResult>),
+    // older than the ttl, show it but refresh it in the background
+    Stale(Vec<SearchResult>),
+    Miss,
+}
+
+impl ResultCache {
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(e) => e.into_inner(),
+        }
+    }
+
+    pub fn get(&self, plugin: &str, query: &str, ttl: Duration) -> CacheLookup {
+        let mut state = self.lock();
+        state.tick += 1;
+        let tick = state.tick;
+        match state.entries.get_mut(&(plugin.to_string(), query.to_string())) {
+            Some(entry) => {
+                entry.last_used = tick;
+                if entry.stored.elapsed() <= ttl {
+                    CacheLookup::Fresh(entry.results.clone())
+                } else {
+                    CacheLookup::Stale(entry.results.clone())
+                }
+            }
+            None => CacheLookup::Miss,
+        }
+    }
+
+    pub fn insert(&self, plugin: &str, query: &str, results: Vec<SearchResult>, capacity: usize) {
+        let mut state = self.lock();
+        state.tick += 1;
+        let tick = state.tick;
+        state.entries.insert(
+            (plugin.to_string(), query.to_string()),
+            CacheEntry {
+                results,
+                stored: std::time::Instant::now(),
+                last_used: tick,
+            },
+        );
+        while state.entries.len() > capacity {
+            let oldest = state.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => {
+                    state.entries.remove(&key);
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONG: Duration = Duration::from_secs(3600);
+
+    fn titles(lookup: CacheLookup) -> Option<Vec<String>> {
+        match lookup {
+            CacheLookup::Fresh(results) => Some(results.iter().map(|r| r.title().to_string()).collect()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let cache = ResultCache::default();
+        cache.insert("files", "a", vec![SearchResult::new("a")], 2);
+        cache.insert("files", "b", vec![SearchResult::new("b")], 2);
+        // reading a makes b the oldest
+        assert_eq!(titles(cache.get("files", "a", LONG)), Some(vec!["a".to_string()]));
+        cache.insert("files", "c", vec![SearchResult::new("c")], 2);
+        assert_eq!(cache.entry_count(), 2);
+        assert!(matches!(cache.get("files", "b", LONG), CacheLookup::Miss));
+        assert!(titles(cache.get("files", "a", LONG)).is_some());
+        assert!(titles(cache.get("files", "c", LONG)).is_some());
+    }
+
+    #[test]
+    fn entries_past_the_ttl_are_stale() {
+        let cache = ResultCache::default();
+        cache.insert("files", "a", vec![SearchResult::new("a")], 8);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(matches!(cache.get("files", "a", Duration::from_millis(1)), CacheLookup::Stale(_)));
+        assert!(matches!(cache.get("files", "a", LONG), CacheLookup::Fresh(_)));
+    }
+
+    #[test]
+    fn keys_are_per_plugin() {
+        let cache = ResultCache::default();
+        cache.insert("files", "a", vec![SearchResult::new("a")], 8);
+        assert!(matches!(cache.get("web", "a", LONG), CacheLookup::Miss));
+    }
+}
@@ -7,9 +7,13 @@ use quick_search_lib::{
     LogLevelOrCustom, LogMessage,
 };
 
-use crate::config::{ConfigLock, PluginConfig};
+use crate::config::{BarAnchor, ConfigLock, DisplayMode, MonitorPlacement, PluginConfig, PreviewPlacement, ResultsPosition};
 
-use super::PluginLoadResult;
+use super::{
+    metrics::{MetricsColumn, PluginMetricsSnapshot},
+    placement::{self, Placement},
+    FontLoader, KeyAction, KeyChord, Keymap, PluginLoadResult, Theme, ThemeBase,
+};
 
 use crate::LOGGER;
 use quick_search_lib::Log;
@@ -19,7 +23,9 @@ pub struct App<'a> {
     loadresults: PluginLoadResult,
     no_plugins_including_missing: bool,
     states: Vec<(String, PluginConfig)>,
-    size: Option<egui::Vec2>,
+    placement: Option<Placement>,
+    // where the window that had focus before the overlay opened is, for MonitorPlacement::FocusedWindow
+    focused_window: Option<(i32, i32)>,
     positioned: bool,
     passthrough: bool,
     force_redraw_now: bool,
@@ -36,6 +42,15 @@ pub struct App<'a> {
     config_backup: Option<crate::config::Config>,
     backlog: BackLog,
     showlogs: u8,
+    debug_view: DebugView,
+    // the column the metrics table is sorted by, and whether it is sorted descending
+    metrics_sort: (MetricsColumn, bool),
+    metrics_export_error: Option<String>,
+    fonts: FontLoader,
+    // names of the connected monitors, offered for MonitorPlacement::Named
+    monitor_names: Vec<String>,
+    // the action the next key press is added to on the keys tab
+    capturing: Option<KeyAction>,
 }
 
 struct BackLog {
@@ -60,7 +75,7 @@ impl BackLog {
                 })
                 .collect::<Vec<_>>(),
         );
-        self.log.sort_by(|a, b| a.1.cmp(&b.1));
+        self.log.sort_by_key(|entry| entry.1);
         self.log.truncate(self.max);
     }
 }
@@ -70,6 +85,8 @@ pub enum Tabs {
     General,
     Plugins,
     Time,
+    Theme,
+    Keys,
     About, // todo
     Debug, // todo
 }
@@ -80,12 +97,29 @@ impl std::fmt::Display for Tabs {
             Tabs::General => write!(f, "General"),
             Tabs::Plugins => write!(f, "Plugins"),
             Tabs::Time => write!(f, "Time"),
+            Tabs::Theme => write!(f, "Theme"),
+            Tabs::Keys => write!(f, "Keys"),
             Tabs::About => write!(f, "About"),
             Tabs::Debug => write!(f, "Debug"),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugView {
+    Logs,
+    Metrics,
+}
+
+impl std::fmt::Display for DebugView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DebugView::Logs => write!(f, "Logs"),
+            DebugView::Metrics => write!(f, "Metrics"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CloseState {
     DoNothing,
@@ -147,7 +181,8 @@ impl App<'_> {
             loadresults,
             states,
             config_lock,
-            size: None,
+            placement: None,
+            focused_window: placement::foreground_center(),
             positioned: false,
             passthrough: false,
             force_redraw_now: false,
@@ -162,6 +197,12 @@ impl App<'_> {
             current_tab: Tabs::General,
             backlog,
             showlogs,
+            debug_view: DebugView::Logs,
+            metrics_sort: (MetricsColumn::P95, true),
+            metrics_export_error: None,
+            fonts: FontLoader::default(),
+            monitor_names: Vec::new(),
+            capturing: None,
         }
     }
 
@@ -170,6 +211,16 @@ impl App<'_> {
             .on_hover_text("Set the delay in ms before the search bar appears after the hotkey is pressed, lower values may cause flickering on some systems.");
         ui.add(egui::Slider::new(&mut self.config_lock.get_mut().total_search_delay, 0..=10000).text("Search delay"))
             .on_hover_text("Set the debounce time in ms, lower values may run excessive searches, higher values mean a longer delay before the search is run.");
+        ui.add(egui::Slider::new(&mut self.config_lock.get_mut().auto_delay_min, 0..=5000).text("Auto delay minimum"))
+            .on_hover_text("Set the shortest delay in ms a plugin with an automatic delay will use");
+        ui.add(egui::Slider::new(&mut self.config_lock.get_mut().auto_delay_max, 0..=10000).text("Auto delay maximum"))
+            .on_hover_text("Set the longest delay in ms a plugin with an automatic delay will use");
+        ui.add(egui::Slider::new(&mut self.config_lock.get_mut().search_timeout, 100..=30000).text("Search timeout"))
+            .on_hover_text("Set the time in ms after which a plugin that is still searching is shown as timed out and counted in the metrics. The search is not cancelled, its results are still shown if they arrive.");
+        ui.add(egui::Slider::new(&mut self.config_lock.get_mut().cache_size, 0..=4096).text("Result cache size"))
+            .on_hover_text("Set how many (plugin, query) results are kept in the result cache, the least recently used ones are dropped first. Set to 0 to disable the cache.");
+        ui.add(egui::Slider::new(&mut self.config_lock.get_mut().worker_threads, 1..=32).text("Search threads"))
+            .on_hover_text("Set the number of threads shared by all plugin searches. Each plugin only ever runs one search at a time, newer queries replace ones that are still waiting.");
         ui.add(egui::Slider::new(&mut self.config_lock.get_mut().truncate_title_length, 25..=250).text("Truncate title length"))
             .on_hover_text("Set the maximum length of the title text for a search result");
         ui.add(egui::Slider::new(&mut self.config_lock.get_mut().truncate_context_length, 25..=250).text("Truncate context length"))
@@ -178,10 +229,175 @@ impl App<'_> {
             .on_hover_text("Set the number of entries around the cursor to display while scrolling. e.g. if set to 2, 5 entries centered around the cursor will be displayed.");
         ui.add(egui::Slider::new(&mut self.config_lock.get_mut().group_entries_while_unselected, 0..=10).text("Entries while unselected"))
             .on_hover_text("Set the number of entries to display from each group while the search bar is not selected. set to 0 to display all entries.");
+        ui.add(
+            egui::Slider::new(&mut self.config_lock.get_mut().results_per_plugin, 0..=10000)
+                .logarithmic(true)
+                .text("Results per plugin"),
+        )
+        .on_hover_text("Set how many results a plugin shows before the rest wait behind a \"show more\" row. Set to 0 to always show everything.");
         ui.add(egui::Slider::new(&mut self.config_lock.get_mut().gap_between_search_bar_and_results, 0.0..=100.0).text("Gap between search bar and results"))
             .on_hover_text("Set the gap between the search bar and the search results, in pixels");
         ui.checkbox(&mut self.config_lock.get_mut().audio_enabled, "Sound effects")
             .on_hover_text("Enable or disable sound effects when the search bar is opened");
+        ui.checkbox(&mut self.config_lock.get_mut().show_plugin_status, "Show plugin status").on_hover_text(
+            "List plugins that have no results on screen with what they are doing: waiting to search, searching, timed out, found nothing, or failed while QuickSearch handled the results",
+        );
+        ui.checkbox(&mut self.config_lock.get_mut().dedup_results, "Merge duplicate results")
+            .on_hover_text("Show a result that several plugins returned only once, under the plugin with the highest priority. Only results with exactly the same title and context are merged. The other plugins can still be picked from the actions menu.");
+        ui.checkbox(&mut self.config_lock.get_mut().show_icons, "Show icons")
+            .on_hover_text("Show icons next to search results and plugin names. Plugins can provide an icon by placing a png with the same name next to the plugin file.");
+        ui.horizontal(|ui| {
+            ui.label("Result layout")
+                .on_hover_text("Grouped shows results under their plugin. Ranked shows every result in a single list ordered by how well it matches the query, with plugin priority giving a small boost. Results that don't match go to the bottom.");
+            ui.separator();
+            for (i, mode) in [DisplayMode::Grouped, DisplayMode::Ranked].into_iter().enumerate() {
+                if i != 0 {
+                    ui.separator();
+                }
+                if ui.selectable_label(self.config_lock.get().display_mode == mode, mode.to_string()).clicked() {
+                    self.config_lock.get_mut().display_mode = mode;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Preview pane")
+                .on_hover_text("Show a preview of the selected result next to the results, or on the other side of the search bar from them");
+            ui.separator();
+            for (i, placement) in [PreviewPlacement::Off, PreviewPlacement::Side, PreviewPlacement::Opposite].into_iter().enumerate() {
+                if i != 0 {
+                    ui.separator();
+                }
+                if ui.selectable_label(self.config_lock.get().preview_placement == placement, placement.to_string()).clicked() {
+                    self.config_lock.get_mut().preview_placement = placement;
+                }
+            }
+        });
+        egui::CollapsingHeader::new("Layout").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Search bar position").on_hover_text("Where the search bar sits on the monitor");
+                ui.separator();
+                for (i, anchor) in [BarAnchor::Center, BarAnchor::TopThird, BarAnchor::Custom].into_iter().enumerate() {
+                    if i != 0 {
+                        ui.separator();
+                    }
+                    if ui.selectable_label(self.config_lock.get().bar_anchor == anchor, anchor.to_string()).clicked() {
+                        self.config_lock.get_mut().bar_anchor = anchor;
+                    }
+                }
+            });
+            let custom = self.config_lock.get().bar_anchor == BarAnchor::Custom;
+            ui.add_enabled(custom, egui::Slider::new(&mut self.config_lock.get_mut().bar_offset_x, -2000.0..=2000.0).text("Horizontal offset"))
+                .on_hover_text("Move the search bar right from the middle of the monitor, negative values move it left");
+            ui.add_enabled(custom, egui::Slider::new(&mut self.config_lock.get_mut().bar_offset_y, -2000.0..=2000.0).text("Vertical offset"))
+                .on_hover_text("Move the search bar down from the middle of the monitor, negative values move it up");
+            ui.add(egui::Slider::new(&mut self.config_lock.get_mut().bar_width, 0.0..=2000.0).text("Search bar width"))
+                .on_hover_text("Set the width of the search bar and results. Set to 0 to size them to their contents.");
+            ui.add(egui::Slider::new(&mut self.config_lock.get_mut().results_max_height, 0.0..=2000.0).text("Maximum results height"))
+                .on_hover_text("Set the height the results scroll within. Set to 0 to use all the space between the search bar and the edge of the monitor.");
+            ui.horizontal(|ui| {
+                ui.label("Results")
+                    .on_hover_text("Show the results below or above the search bar, history search and the full result go on the other side");
+                ui.separator();
+                for (i, position) in [ResultsPosition::Below, ResultsPosition::Above].into_iter().enumerate() {
+                    if i != 0 {
+                        ui.separator();
+                    }
+                    if ui.selectable_label(self.config_lock.get().results_position == position, position.to_string()).clicked() {
+                        self.config_lock.get_mut().results_position = position;
+                    }
+                }
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Monitor").on_hover_text("Which monitor the search bar opens on");
+            ui.separator();
+            for (i, placement) in [MonitorPlacement::Primary, MonitorPlacement::Mouse, MonitorPlacement::FocusedWindow, MonitorPlacement::Named]
+                .into_iter()
+                .enumerate()
+            {
+                if i != 0 {
+                    ui.separator();
+                }
+                if ui.selectable_label(self.config_lock.get().monitor_placement == placement, placement.to_string()).clicked() {
+                    self.config_lock.get_mut().monitor_placement = placement;
+                }
+            }
+        });
+        if self.config_lock.get().monitor_placement == MonitorPlacement::Named {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.config_lock.get_mut().monitor_name).hint_text("Monitor name").desired_width(200.0))
+                    .on_hover_text("The primary monitor is used while no connected monitor has this name");
+                for name in self.monitor_names.iter() {
+                    ui.separator();
+                    if ui.selectable_label(self.config_lock.get().monitor_name == *name, name).clicked() {
+                        self.config_lock.get_mut().monitor_name = name.clone();
+                    }
+                }
+            });
+        }
+        ui.add(egui::Slider::new(&mut self.config_lock.get_mut().preview_delay, 0..=2000).text("Preview delay"))
+            .on_hover_text("Set the time in ms the cursor has to rest on a result before its preview is loaded");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.config_lock.get_mut().frecency_enabled, "Learn from usage")
+                .on_hover_text("Remember which results are opened, and move results that are opened often and recently to the top of their group");
+            ui.separator();
+            if ui
+                .button(format!("Clear history ({})", crate::USAGE_STORE.entry_count()))
+                .on_hover_text("Forget every result that has been opened")
+                .clicked()
+            {
+                crate::USAGE_STORE.clear();
+            }
+        });
+        ui.add_enabled(
+            self.config_lock.get().frecency_enabled,
+            egui::Checkbox::new(&mut self.config_lock.get_mut().frecency_preselect, "Preselect likely result"),
+        )
+        .on_hover_text("When entering the results, place the cursor on the result you are most likely to pick instead of the first one");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.config_lock.get_mut().query_history_enabled, "Query history")
+                .on_hover_text("Remember submitted queries, the keys tab sets the keys that cycle through and search them.");
+            ui.separator();
+            if ui
+                .button(format!("Clear query history ({})", crate::QUERY_HISTORY.entry_count()))
+                .on_hover_text("Forget every submitted query")
+                .clicked()
+            {
+                crate::QUERY_HISTORY.clear();
+            }
+        });
+        ui.add_enabled(
+            self.config_lock.get().query_history_enabled,
+            egui::Slider::new(&mut self.config_lock.get_mut().query_history_size, 1..=1000).text("Query history size"),
+        )
+        .on_hover_text("Set how many submitted queries are remembered, the oldest ones are dropped first");
+
+        egui::CollapsingHeader::new("Fallbacks")
+            .show(ui, |ui| {
+                ui.label("Offered when no plugin finds anything. {query} is replaced with the query in both the name and the url. Only http and https urls are opened.");
+                let fallbacks = &mut self.config_lock.get_mut().fallbacks;
+                let mut remove = None;
+                for (i, fallback) in fallbacks.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut fallback.name).hint_text("Name").desired_width(200.0));
+                        ui.add(egui::TextEdit::singleline(&mut fallback.url).hint_text("https://example.com/?q={query}").desired_width(300.0));
+                        if ui.button(RichText::new("Remove").color(Color32::RED)).clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    fallbacks.remove(i);
+                }
+                if ui.button("Add fallback").clicked() {
+                    fallbacks.push(crate::config::FallbackTemplate {
+                        name: "Search for {query}".to_string(),
+                        url: String::new(),
+                    });
+                }
+            })
+            .header_response
+            .on_hover_text("Actions offered when no plugin returns any results");
 
         if let Some(ref mut autolaunchinfo) = self.autolaunchinfo {
             ui.horizontal(|ui| {
@@ -220,6 +436,7 @@ impl App<'_> {
                 .column(Column::auto().resizable(false))
                 .column(Column::auto().resizable(false))
                 .column(Column::auto().resizable(false))
+                .column(Column::auto().resizable(false))
                 .column(Column::remainder())
                 .header(20.0, |mut header| {
                     header.col(|ui| {
@@ -229,7 +446,10 @@ impl App<'_> {
                         ui.add(nowrap_heading("Priority")).on_hover_text("Set the priority of the plugin (higher priority shows up above lower priority)");
                     });
                     header.col(|ui| {
-                        ui.add(nowrap_heading("Delay")).on_hover_text("Set the delay in ms before the plugin is queried after the search bar changes. Lower values may cause excessive queries, higher values may cause the plugin to be slow to respond.");
+                        ui.add(nowrap_heading("Delay")).on_hover_text("Set the delay in ms before the plugin is queried after the search bar changes. Lower values may cause excessive queries, higher values may cause the plugin to be slow to respond. Auto picks the delay from how fast the plugin answers and how fast you type, within the auto delay minimum and maximum from the general tab.");
+                    });
+                    header.col(|ui| {
+                        ui.add(nowrap_heading("Cache")).on_hover_text("Enable or disable caching results for the plugin, and set how many seconds a cached result is used before it is refreshed in the background.");
                     });
                 })
                 .body(|mut body| {
@@ -261,7 +481,7 @@ impl App<'_> {
                 "Invalid format string".to_owned()
             })
             .size(self.config_lock.get().time_font_size)
-            .color(egui::Color32::LIGHT_RED),
+            .color(self.config_lock.get().theme.clock()),
         ))
         .on_hover_text("Current time display");
         let lower_case = self.tz_search_string.to_lowercase().replace(['_', '-', '/', ' '], "");
@@ -293,6 +513,207 @@ impl App<'_> {
         });
     }
 
+    fn theme_tab(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Presets").on_hover_text("Replace every setting below with a built in theme");
+            ui.separator();
+            for (i, (name, preset)) in Theme::presets().into_iter().enumerate() {
+                if i != 0 {
+                    ui.separator();
+                }
+                if ui.selectable_label(self.config_lock.get().theme == preset, name).clicked() {
+                    self.config_lock.get_mut().theme = preset;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Base")
+                .on_hover_text("The built in egui style used for everything the theme doesn't set, like buttons and sliders");
+            ui.separator();
+            for (i, base) in [ThemeBase::Dark, ThemeBase::Light].into_iter().enumerate() {
+                if i != 0 {
+                    ui.separator();
+                }
+                if ui.selectable_label(self.config_lock.get().theme.base == base, base.to_string()).clicked() {
+                    self.config_lock.get_mut().theme.base = base;
+                }
+            }
+        });
+        egui::Grid::new("theme_colors").num_columns(2).show(ui, |ui| {
+            let theme = &mut self.config_lock.get_mut().theme;
+            for (name, color, hover) in [
+                ("Background", &mut theme.background, "The background of the search bar and every window"),
+                ("Text", &mut theme.text, "Plain text, like results that are not under the cursor"),
+                ("Selection", &mut theme.selection, "The title of the result under the cursor, and selected text"),
+                ("Context", &mut theme.context, "The context line of the result under the cursor"),
+                ("Clock", &mut theme.clock, "The clock above the search bar"),
+                (
+                    "Group header",
+                    &mut theme.group_header,
+                    "The result count next to a plugin's name, the name itself is colored by the plugin",
+                ),
+                ("Separator", &mut theme.separator, "Separator lines and window borders"),
+                ("Marked", &mut theme.marked, "The check mark on results marked to be executed together"),
+                ("Warning", &mut theme.warning, "Plugins that are taking longer than the search timeout"),
+                ("Error", &mut theme.error, "Plugins whose results QuickSearch failed to handle"),
+            ] {
+                ui.label(name).on_hover_text(hover);
+                ui.color_edit_button_srgb(color);
+                ui.end_row();
+            }
+        });
+        ui.add(egui::Slider::new(&mut self.config_lock.get_mut().theme.rounding, 0.0..=20.0).text("Rounding"))
+            .on_hover_text("Set how round the corners of windows are");
+        ui.add(egui::Slider::new(&mut self.config_lock.get_mut().theme.opacity, 0.2..=1.0).text("Opacity"))
+            .on_hover_text("Set how opaque window backgrounds are, text stays fully opaque");
+        egui::CollapsingHeader::new("Fonts")
+            .show(ui, |ui| {
+                ui.label("Font files are tried in order for every character, egui's own fonts are always kept as a last resort.");
+                let fonts = &mut self.config_lock.get_mut().fonts;
+                let mut remove = None;
+                for (i, file) in fonts.files.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(file).hint_text("C:/Windows/Fonts/msyh.ttc").desired_width(400.0));
+                        if ui.button(RichText::new("Remove").color(Color32::RED)).clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    fonts.files.remove(i);
+                }
+                if ui.button("Add font").clicked() {
+                    fonts.files.push(String::new());
+                }
+                ui.checkbox(&mut fonts.prefer_custom, "Prefer custom fonts")
+                    .on_hover_text("Use the font files for all text, otherwise they only fill in characters egui's fonts can't draw, like CJK or emoji");
+                ui.add(egui::Slider::new(&mut fonts.search_input_size, 8.0..=32.0).text("Search input size"))
+                    .on_hover_text("Set the font size of the search bar");
+                ui.add(egui::Slider::new(&mut fonts.title_size, 8.0..=32.0).text("Result title size"))
+                    .on_hover_text("Set the font size of result titles");
+                ui.add(egui::Slider::new(&mut fonts.context_size, 8.0..=32.0).text("Result context size"))
+                    .on_hover_text("Set the font size of the context line under the selected result");
+                ui.add(egui::Slider::new(&mut fonts.group_header_size, 8.0..=32.0).text("Group header size"))
+                    .on_hover_text("Set the font size of plugin names and result counts above each group");
+                for error in self.fonts.errors() {
+                    ui.label(RichText::new(error).color(Color32::RED));
+                }
+            })
+            .header_response
+            .on_hover_text("Load extra font files and set the size of each part of the results");
+        ui.separator();
+        ui.label("Preview");
+        self.theme_preview(ui);
+    }
+
+    // a still image of the search bar and a few results, drawn with the theme being edited
+    fn theme_preview(&self, ui: &mut egui::Ui) {
+        let theme = &self.config_lock.get().theme;
+        let fonts = &self.config_lock.get().fonts;
+        egui::Frame::window(ui.style()).show(ui, |ui| {
+            ui.vertical_centered(|ui| {
+                if self.config_lock.get().clock_enabled {
+                    ui.label(RichText::new("12:34").size(self.config_lock.get().time_font_size).color(theme.clock()));
+                    ui.separator();
+                }
+                let mut query = "quick search".to_string();
+                ui.add_enabled(
+                    false,
+                    egui::TextEdit::singleline(&mut query)
+                        .font(egui::FontId::proportional(fonts.search_input_size))
+                        .horizontal_align(egui::Align::Center),
+                );
+            });
+        });
+        ui.add_space(self.config_lock.get().gap_between_search_bar_and_results);
+        egui::Frame::window(ui.style()).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("▼").size(fonts.group_header_size).color(theme.group_header()));
+                ui.label(RichText::new("Files").size(fonts.group_header_size));
+                ui.separator();
+                ui.label(RichText::new("3 Results").size(fonts.group_header_size).color(theme.group_header()));
+            });
+            ui.separator();
+            ui.label(RichText::new("quick search notes.txt").size(fonts.title_size).color(theme.selection()));
+            ui.label(RichText::new("C:/Users/me/Documents/quick search notes.txt").size(fonts.context_size).color(theme.context()));
+            ui.label(RichText::new("quick search.exe").size(fonts.title_size));
+            ui.label(RichText::new("クイック検索 🔍").size(fonts.title_size));
+        });
+    }
+
+    fn keys_tab(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Presets")
+                .on_hover_text("Replace every binding with a built in keymap. The emacs and vim keymaps add their keys to the default ones.");
+            ui.separator();
+            for (i, (name, preset)) in Keymap::presets().into_iter().enumerate() {
+                if i != 0 {
+                    ui.separator();
+                }
+                if ui.selectable_label(self.config_lock.get().keymap == preset, name).clicked() {
+                    self.config_lock.get_mut().keymap = preset;
+                }
+            }
+        });
+        ui.label("Keys that type text, like plain letters, only work while moving through the results.");
+
+        // the key press is taken before any widget sees it, so enter, space and escape can be bound too
+        if let Some(action) = self.capturing {
+            let captured = ui.input_mut(|i| {
+                let mut captured = None;
+                i.events.retain(|event| match event {
+                    egui::Event::Key { key, modifiers, pressed: true, .. } if captured.is_none() => {
+                        captured = Some(KeyChord::from_event(*key, *modifiers));
+                        false
+                    }
+                    _ => true,
+                });
+                captured
+            });
+            if let Some(chord) = captured {
+                self.config_lock.get_mut().keymap.add(action, chord);
+                self.capturing = None;
+            }
+        }
+
+        let mut remove = None;
+        let mut reset = None;
+        egui::Grid::new("keymap").num_columns(2).striped(true).show(ui, |ui| {
+            for action in KeyAction::ALL {
+                ui.label(action.to_string()).on_hover_text(action.description());
+                ui.horizontal(|ui| {
+                    for chord in self.config_lock.get().keymap.chords(action) {
+                        if ui.button(chord.to_string()).on_hover_text("Click to remove").clicked() {
+                            remove = Some((action, *chord));
+                        }
+                    }
+                    if self.capturing == Some(action) {
+                        if ui.button(RichText::new("Press a key...").weak()).on_hover_text("Click to cancel").clicked() {
+                            self.capturing = None;
+                        }
+                    } else if ui.button("+").on_hover_text("Click, then press the keys to add").clicked() {
+                        self.capturing = Some(action);
+                    }
+                    if ui.button("Reset").on_hover_text("Go back to this action's default keys").clicked() {
+                        reset = Some(action);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+        if let Some((action, chord)) = remove {
+            self.config_lock.get_mut().keymap.remove(action, chord);
+        }
+        if let Some(action) = reset {
+            self.config_lock.get_mut().keymap.reset(action);
+        }
+
+        for (chord, actions) in self.config_lock.get().keymap.conflicts() {
+            let names = actions.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ");
+            ui.label(RichText::new(format!("{} is bound to more than one action: {}", chord, names)).color(Color32::YELLOW));
+        }
+    }
+
     fn about_tab(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
             ui.label("About");
@@ -300,6 +721,89 @@ impl App<'_> {
     }
 
     fn debug_tab(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for (i, view) in [DebugView::Logs, DebugView::Metrics].into_iter().enumerate() {
+                if i != 0 {
+                    ui.separator();
+                }
+                if ui.add(egui::SelectableLabel::new(self.debug_view == view, view.to_string())).clicked() {
+                    self.debug_view = view;
+                }
+            }
+        });
+        ui.separator();
+        match self.debug_view {
+            DebugView::Logs => self.logs_view(ui),
+            DebugView::Metrics => self.metrics_view(ui),
+        }
+    }
+
+    fn metrics_view(&mut self, ui: &mut egui::Ui) {
+        let mut rows: Vec<PluginMetricsSnapshot> = crate::PLUGIN_METRICS.snapshot();
+        let (sort_column, descending) = self.metrics_sort;
+        sort_column.sort(&mut rows);
+        if descending {
+            rows.reverse();
+        }
+
+        let pool = crate::SEARCH_POOL.stats();
+        ui.horizontal(|ui| {
+            ui.label(format!("Search threads: {} | Running: {} | Queued: {}", pool.workers, pool.in_flight, pool.queued))
+                .on_hover_text("The state of the shared search worker pool");
+            ui.separator();
+            ui.label(format!("Cached results: {}", crate::RESULT_CACHE.entry_count()));
+            if ui.button("Clear cache").clicked() {
+                crate::RESULT_CACHE.clear();
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Export JSON").on_hover_text("Save the current plugin metrics to a JSON file").clicked() {
+                self.metrics_export_error = export_metrics(&rows).err();
+            }
+            if let Some(error) = &self.metrics_export_error {
+                ui.label(RichText::new(error).color(Color32::RED));
+            }
+        });
+        ui.separator();
+
+        if rows.is_empty() {
+            ui.label("No searches have been run yet");
+            return;
+        }
+
+        let mut builder = TableBuilder::new(ui).max_scroll_height(360.0);
+        for _ in MetricsColumn::ALL {
+            builder = builder.column(Column::auto().resizable(true));
+        }
+        builder
+            .header(20.0, |mut header| {
+                for column in MetricsColumn::ALL {
+                    header.col(|ui| {
+                        let text = if column == sort_column {
+                            format!("{} {}", column, if descending { "⏷" } else { "⏶" })
+                        } else {
+                            column.to_string()
+                        };
+                        if ui.add(Button::new(RichText::new(text).strong()).frame(false).wrap(false)).on_hover_text(column.hover_text()).clicked() {
+                            self.metrics_sort = if column == sort_column { (column, !descending) } else { (column, true) };
+                        }
+                    });
+                }
+            })
+            .body(|mut body| {
+                for row in rows.iter() {
+                    body.row(20.0, |mut table_row| {
+                        for column in MetricsColumn::ALL {
+                            table_row.col(|ui| {
+                                ui.add(Label::new(column.cell(row)).wrap(false));
+                            });
+                        }
+                    });
+                }
+            });
+    }
+
+    fn logs_view(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
             ui.label("Debug");
             ui.separator();
@@ -338,6 +842,7 @@ impl App<'_> {
     }
 
     fn show_states(&mut self, body: &mut egui_extras::TableBody<'_>, midwindowx: i32, midwindowy: i32, egui_context: &egui::Context) {
+        let (auto_delay_min, auto_delay_max) = (self.config_lock.get().auto_delay_min, self.config_lock.get().auto_delay_max);
         self.states
             .iter_mut()
             .enumerate()
@@ -383,7 +888,24 @@ impl App<'_> {
                         ui.add(egui::Slider::new(&mut state.priority, 0..=128));
                     });
                     row.col(|ui| {
-                        ui.add(egui::Slider::new(&mut state.delay, 0..=10000));
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut state.auto_delay, "Auto");
+                            ui.add_enabled(!state.auto_delay, egui::Slider::new(&mut state.delay, 0..=10000));
+                            if state.auto_delay {
+                                match crate::PLUGIN_METRICS.auto_delay(name, auto_delay_min, auto_delay_max) {
+                                    Some(delay) => ui
+                                        .label(format!("{}ms", delay))
+                                        .on_hover_text("The delay picked from the plugin's recent latency and your typing speed"),
+                                    None => ui.label("no data").on_hover_text("The plugin has not been searched yet, the fixed delay is used until it has"),
+                                };
+                            }
+                        });
+                    });
+                    row.col(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut state.cache_enabled, "");
+                            ui.add_enabled(state.cache_enabled, egui::Slider::new(&mut state.cache_ttl, 0..=3600).suffix("s"));
+                        });
                     });
                 })
             });
@@ -492,9 +1014,9 @@ fn show_log_message(ui: &mut egui::Ui, i: &LogMessage, show_bits: &u8, timezone:
     ui.horizontal(|ui| {
         ui.label(
             RichText::new(
-                chrono::NaiveDateTime::from_timestamp_millis(i.time.get() as i64)
+                chrono::DateTime::from_timestamp_millis(i.time.get() as i64)
                     .and_then(|d| {
-                        match d.and_local_timezone(timezone) {
+                        match d.naive_utc().and_local_timezone(timezone) {
                             chrono::LocalResult::Ambiguous(v, _) => Some(v),
                             chrono::LocalResult::Single(v) => Some(v),
                             _ => None,
@@ -540,14 +1062,15 @@ impl<'a> egui_overlay::EguiOverlay for App<'a> {
         glfw_backend: &mut egui_overlay::egui_window_glfw_passthrough::GlfwBackend,
     ) {
         self.backlog.drain_global();
-        if self.size.is_none() {
+        if self.placement.is_none() {
             glfw_backend.glfw.with_connected_monitors(|_glfw, monitors| {
-                let monitor = monitors.first();
-                match monitor {
+                self.monitor_names = placement::names(monitors);
+                let config = self.config_lock.get();
+                match placement::pick(monitors, config.monitor_placement, &config.monitor_name, self.focused_window) {
                     None => {
                         LOGGER.error("no monitor");
                     }
-                    Some(monitor) => {
+                    Some(placement) => {
                         glfw_backend.window.show();
                         glfw_backend.window.set_mouse_passthrough(true);
                         glfw_backend.window.set_title("QuickSearch Config");
@@ -566,32 +1089,30 @@ impl<'a> egui_overlay::EguiOverlay for App<'a> {
                             glfw_backend.window.set_should_close(true);
                         }
 
-                        let (x1, y1, x2, y2) = monitor.get_workarea();
-                        LOGGER.info(&format!("monitor workarea: {}x{} {}x{}", x1, y1, x2, y2));
-                        self.size = Some(egui::Vec2::new(x2 as f32, y2 as f32));
+                        self.placement = Some(placement);
                     }
                 }
             });
         } else if self.time.elapsed().as_millis() > self.config_lock.get().appearance_delay as u128 {
-            if let Some(size) = self.size {
+            if let Some(placement) = &self.placement {
                 if !self.positioned {
-                    glfw_backend.window.set_pos(0, 0);
-                    glfw_backend.window.set_size(size.x as i32 - 1, size.y as i32 - 1);
+                    glfw_backend.window.set_pos(placement.x, placement.y);
+                    glfw_backend.window.set_size(placement.width - 1, placement.height - 1);
                     self.positioned = true;
                 }
+                egui_context.set_pixels_per_point(placement.scale);
             }
 
+            // the window size is in pixels, egui lays out in points
             let (midwindowx, midwindowy) = {
                 let (x, y) = glfw_backend.window.get_size();
-                (x / 2, y / 2)
+                let scale = egui_context.pixels_per_point();
+                ((x as f32 / scale) as i32 / 2, (y as f32 / scale) as i32 / 2)
             };
 
-            egui_context.set_visuals({
-                let mut visuals = egui::Visuals::dark();
-                visuals.popup_shadow.extrusion = 0.0;
-                visuals.window_shadow.extrusion = 0.0;
-                visuals
-            });
+            // the config window wears the theme and fonts too, so edits on the theme tab show up right away
+            self.fonts.apply(egui_context, &self.config_lock.get().fonts);
+            egui_context.set_visuals(self.config_lock.get().theme.visuals());
 
             egui::Window::new("Config")
                 .title_bar(false)
@@ -599,7 +1120,7 @@ impl<'a> egui_overlay::EguiOverlay for App<'a> {
                 .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::new(0., 0.))
                 .show(egui_context, |ui| {
                     ui.horizontal(|ui| {
-                        for (i, tab) in [Tabs::General, Tabs::Plugins, Tabs::Time, Tabs::About, Tabs::Debug].into_iter().enumerate() {
+                        for (i, tab) in [Tabs::General, Tabs::Plugins, Tabs::Time, Tabs::Theme, Tabs::Keys, Tabs::About, Tabs::Debug].into_iter().enumerate() {
                             if i != 0 {
                                 ui.separator();
                             }
@@ -620,6 +1141,12 @@ impl<'a> egui_overlay::EguiOverlay for App<'a> {
                         Tabs::Time => {
                             self.time_tab(ui);
                         }
+                        Tabs::Theme => {
+                            self.theme_tab(ui);
+                        }
+                        Tabs::Keys => {
+                            self.keys_tab(ui);
+                        }
                         Tabs::About => {
                             self.about_tab(ui);
                         }
@@ -695,6 +1222,32 @@ impl<'a> egui_overlay::EguiOverlay for App<'a> {
     }
 }
 
+fn export_metrics(rows: &[PluginMetricsSnapshot]) -> Result<(), String> {
+    let path = match rfd::FileDialog::new().set_file_name("plugin-metrics.json").add_filter("JSON", &["json"]).save_file() {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    let json = match serde_json::to_string_pretty(rows) {
+        Ok(json) => json,
+        Err(e) => {
+            let error = format!("Failed to serialize metrics: {}", e);
+            LOGGER.error(&error);
+            return Err(error);
+        }
+    };
+    match std::fs::write(&path, json) {
+        Ok(_) => {
+            LOGGER.info(&format!("Exported metrics to {:?}", path));
+            Ok(())
+        }
+        Err(e) => {
+            let error = format!("Failed to write metrics file: {}", e);
+            LOGGER.error(&error);
+            Err(error)
+        }
+    }
+}
+
 fn nowrap_heading(text: &str) -> Label {
     Label::new(RichText::new(text).heading()).wrap(false)
 }
@@ -0,0 +1,96 @@
+use egui::{FontData, FontDefinitions, FontFamily};
+use serde::{Deserialize, Serialize};
+
+use crate::LOGGER;
+use quick_search_lib::Log;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct FontSettings {
+    // ttf/otf files, tried in order for every character
+    pub files: Vec<String>,
+    // put the files in front of egui's fonts so they style all text, otherwise they only fill in characters egui's fonts lack, like cjk
+    pub prefer_custom: bool,
+    pub search_input_size: f32,
+    pub title_size: f32,
+    pub context_size: f32,
+    pub group_header_size: f32,
+}
+
+impl Default for FontSettings {
+    // the sizes egui used before they were configurable
+    fn default() -> Self {
+        FontSettings {
+            files: Vec::new(),
+            prefer_custom: false,
+            search_input_size: 12.5,
+            title_size: 12.5,
+            context_size: 12.5,
+            group_header_size: 12.5,
+        }
+    }
+}
+
+// loading fonts reads every file and makes egui rebuild its atlas, so it only happens when the file list changes
+#[derive(Default)]
+pub struct FontLoader {
+    loaded: Option<(Vec<String>, bool)>,
+    errors: Vec<String>,
+}
+
+impl FontLoader {
+    pub fn apply(&mut self, egui_context: &egui::Context, settings: &FontSettings) {
+        let wanted = (settings.files.clone(), settings.prefer_custom);
+        if self.loaded.as_ref() == Some(&wanted) {
+            return;
+        }
+        let (definitions, errors) = definitions(settings);
+        egui_context.set_fonts(definitions);
+        self.errors = errors;
+        self.loaded = Some(wanted);
+    }
+
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+}
+
+fn definitions(settings: &FontSettings) -> (FontDefinitions, Vec<String>) {
+    let mut definitions = FontDefinitions::default();
+    let mut errors = Vec::new();
+    let mut loaded = Vec::new();
+    for path in settings.files.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        match std::fs::read(path) {
+            // egui panics on a font it can't parse, so every file is parsed the way egui will before it is handed over
+            Ok(bytes) if ab_glyph::FontRef::try_from_slice(&bytes).is_err() => {
+                let error = format!("{} is not a ttf or otf font, or it is damaged", path);
+                LOGGER.error(&error);
+                errors.push(error);
+            }
+            Ok(bytes) => {
+                LOGGER.info(&format!("Loaded font {}", path));
+                let name = format!("custom:{}", path);
+                definitions.font_data.insert(name.clone(), FontData::from_owned(bytes));
+                loaded.push(name);
+            }
+            Err(e) => {
+                let error = format!("Failed to load font {}: {}", path, e);
+                LOGGER.error(&error);
+                errors.push(error);
+            }
+        }
+    }
+
+    if let Some(proportional) = definitions.families.get_mut(&FontFamily::Proportional) {
+        if settings.prefer_custom {
+            proportional.splice(0..0, loaded.iter().cloned());
+        } else {
+            proportional.extend(loaded.iter().cloned());
+        }
+    }
+    // monospace keeps its own font first either way, proportional glyphs would break the alignment
+    if let Some(monospace) = definitions.families.get_mut(&FontFamily::Monospace) {
+        monospace.extend(loaded);
+    }
+    (definitions, errors)
+}
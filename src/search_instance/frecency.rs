@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use quick_search_lib::SearchResult;
+use serde::{Deserialize, Serialize};
+
+use crate::LOGGER;
+use quick_search_lib::Log;
+
+// the store is capped so the file stays small, the entries with the lowest score are dropped first
+const MAX_ENTRIES: usize = 1000;
+// how many of the queries a result was picked from are remembered
+const MAX_QUERIES: usize = 8;
+// only the start of the query is stored, that is what the user types before picking the same thing again
+const QUERY_PREFIX_LENGTH: usize = 16;
+
+// by plugin, then by title and context, so scoring a result is a lookup instead of a scan
+type UsageMap = HashMap<String, HashMap<(String, String), UsageEntry>>;
+
+// remembers which results were executed, so results that are picked often and recently can be moved up
+pub struct UsageStore {
+    entries: Mutex<UsageMap>,
+    // bumped on every change, a background write that a newer one has already superseded is skipped
+    version: Arc<AtomicU64>,
+    writing: Arc<Mutex<()>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct UsageEntry {
+    plugin: String,
+    title: String,
+    context: String,
+    queries: Vec<String>,
+    count: u32,
+    // unix timestamp in seconds
+    last_used: i64,
+}
+
+// holds the store's lock while a group or the whole result list is scored, so it is only taken once
+pub struct UsageScorer<'a> {
+    entries: MutexGuard<'a, UsageMap>,
+    query: String,
+    now: i64,
+}
+
+impl UsageStore {
+    fn file() -> std::path::PathBuf {
+        crate::DIRECTORY.data_dir().join("usage.json")
+    }
+
+    pub fn load() -> Self {
+        let entries: Vec<UsageEntry> = match std::fs::read_to_string(Self::file()) {
+            Ok(data) => match serde_json::from_str(&data) {
+                Ok(entries) => {
+                    LOGGER.info("Loaded usage history");
+                    entries
+                }
+                Err(e) => {
+                    LOGGER.error(&format!("Failed to parse usage history: {}", e));
+                    Vec::new()
+                }
+            },
+            Err(e) => {
+                LOGGER.info(&format!("No usage history loaded: {}", e));
+                Vec::new()
+            }
+        };
+        Self {
+            entries: Mutex::new(index(entries)),
+            version: Arc::default(),
+            writing: Arc::default(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, UsageMap> {
+        match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(e) => e.into_inner(),
+        }
+    }
+
+    // executing a result shouldn't wait on the disk, so the file is written from a thread of its own
+    fn save_in_background(&self, entries: &UsageMap) {
+        let snapshot = entries.values().flat_map(|entries| entries.values().cloned()).collect::<Vec<_>>();
+        let version = self.version.fetch_add(1, Ordering::SeqCst) + 1;
+        let latest = Arc::clone(&self.version);
+        let writing = Arc::clone(&self.writing);
+        std::thread::spawn(move || {
+            let _writing = match writing.lock() {
+                Ok(guard) => guard,
+                Err(e) => e.into_inner(),
+            };
+            if latest.load(Ordering::SeqCst) == version {
+                Self::save(&snapshot);
+            }
+        });
+    }
+
+    fn save(entries: &[UsageEntry]) {
+        let data = match serde_json::to_string(entries) {
+            Ok(data) => data,
+            Err(e) => {
+                LOGGER.error(&format!("Failed to serialize usage history: {}", e));
+                return;
+            }
+        };
+        match std::fs::write(Self::file(), data) {
+            Ok(_) => LOGGER.trace("Wrote usage history"),
+            Err(e) => LOGGER.error(&format!("Failed to write usage history: {}", e)),
+        }
+    }
+
+    pub fn record(&self, plugin: &str, result: &SearchResult, query: &str) {
+        let now = chrono::Utc::now().timestamp();
+        let query = query_prefix(query);
+        let mut entries = self.lock();
+        let key = (result.title().to_string(), result.context().to_string());
+        match entries.entry(plugin.to_string()).or_default().entry(key) {
+            std::collections::hash_map::Entry::Occupied(mut entry) => {
+                let entry = entry.get_mut();
+                entry.count = entry.count.saturating_add(1);
+                entry.last_used = now;
+                entry.queries.retain(|q| *q != query);
+                entry.queries.insert(0, query);
+                entry.queries.truncate(MAX_QUERIES);
+            }
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(UsageEntry {
+                    plugin: plugin.to_string(),
+                    title: result.title().to_string(),
+                    context: result.context().to_string(),
+                    queries: vec![query],
+                    count: 1,
+                    last_used: now,
+                });
+            }
+        }
+        if entries.values().map(HashMap::len).sum::<usize>() > MAX_ENTRIES {
+            let mut all = entries.drain().flat_map(|(_, entries)| entries.into_values()).collect::<Vec<_>>();
+            all.sort_by(|a, b| b.frecency(now).total_cmp(&a.frecency(now)));
+            all.truncate(MAX_ENTRIES);
+            *entries = index(all);
+        }
+        self.save_in_background(&entries);
+    }
+
+    pub fn scorer(&self, query: &str) -> UsageScorer<'_> {
+        UsageScorer {
+            entries: self.lock(),
+            query: query_prefix(query),
+            now: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.lock().values().map(HashMap::len).sum()
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.clear();
+        self.save_in_background(&entries);
+    }
+}
+
+impl UsageScorer<'_> {
+    // groups from plugins that were never picked from can skip scoring altogether
+    pub fn knows(&self, plugin: &str) -> bool {
+        self.entries.get(plugin).map(|entries| !entries.is_empty()).unwrap_or(false)
+    }
+
+    // 0.0 for results that were never picked
+    pub fn score(&self, plugin: &str, result: &SearchResult) -> f32 {
+        let entry = match self.entries.get(plugin).and_then(|entries| entries.get(&(result.title().to_string(), result.context().to_string()))) {
+            Some(entry) => entry,
+            None => return 0.0,
+        };
+        // picking the same result for the same query is a much stronger signal than having picked it at all
+        let query_bonus = if entry.queries.iter().any(|q| q.starts_with(&self.query) || self.query.starts_with(q.as_str())) {
+            2.0
+        } else {
+            1.0
+        };
+        entry.frecency(self.now) * query_bonus
+    }
+}
+
+impl UsageEntry {
+    fn frecency(&self, now: i64) -> f32 {
+        let days = (now - self.last_used).max(0) / 86400;
+        let recency = match days {
+            0..=3 => 100.0,
+            4..=13 => 70.0,
+            14..=30 => 50.0,
+            31..=89 => 30.0,
+            _ => 10.0,
+        };
+        self.count as f32 * recency
+    }
+}
+
+fn index(entries: Vec<UsageEntry>) -> UsageMap {
+    let mut map = UsageMap::new();
+    for entry in entries {
+        map.entry(entry.plugin.clone()).or_default().insert((entry.title.clone(), entry.context.clone()), entry);
+    }
+    map
+}
+
+fn query_prefix(query: &str) -> String {
+    query.trim().to_lowercase().chars().take(QUERY_PREFIX_LENGTH).collect()
+}
@@ -0,0 +1,122 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use crate::LOGGER;
+use quick_search_lib::Log;
+
+// submitted queries, newest first, kept in the data dir so config.toml stays small and readable
+pub struct QueryHistory {
+    entries: Mutex<Vec<String>>,
+    // bumped on every change, a background write is skipped if a newer one is already on its way
+    version: Arc<AtomicU64>,
+    // one write at a time, so an older snapshot can't land after a newer one
+    writing: Arc<Mutex<()>>,
+}
+
+impl QueryHistory {
+    fn file() -> std::path::PathBuf {
+        crate::DIRECTORY.data_dir().join("history.json")
+    }
+
+    pub fn load() -> Self {
+        let entries = match std::fs::read_to_string(Self::file()) {
+            Ok(data) => match serde_json::from_str(&data) {
+                Ok(entries) => {
+                    LOGGER.info("Loaded query history");
+                    entries
+                }
+                Err(e) => {
+                    LOGGER.error(&format!("Failed to parse query history: {}", e));
+                    Vec::new()
+                }
+            },
+            Err(e) => {
+                LOGGER.info(&format!("No query history loaded: {}", e));
+                Vec::new()
+            }
+        };
+        Self {
+            entries: Mutex::new(entries),
+            version: Arc::default(),
+            writing: Arc::default(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<String>> {
+        match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(e) => e.into_inner(),
+        }
+    }
+
+    // submitting a query shouldn't wait on the disk, so the file is written from a thread of its own
+    fn save_in_background(&self, entries: &[String]) {
+        let snapshot = entries.to_vec();
+        let version = self.version.fetch_add(1, Ordering::SeqCst) + 1;
+        let latest = Arc::clone(&self.version);
+        let writing = Arc::clone(&self.writing);
+        std::thread::spawn(move || {
+            let _writing = match writing.lock() {
+                Ok(guard) => guard,
+                Err(e) => e.into_inner(),
+            };
+            if latest.load(Ordering::SeqCst) == version {
+                Self::save(&snapshot);
+            }
+        });
+    }
+
+    fn save(entries: &[String]) {
+        let data = match serde_json::to_string(entries) {
+            Ok(data) => data,
+            Err(e) => {
+                LOGGER.error(&format!("Failed to serialize query history: {}", e));
+                return;
+            }
+        };
+        match std::fs::write(Self::file(), data) {
+            Ok(_) => LOGGER.trace("Wrote query history"),
+            Err(e) => LOGGER.error(&format!("Failed to write query history: {}", e)),
+        }
+    }
+
+    // moves the query to the front if it was already in the history
+    pub fn push(&self, query: &str, capacity: usize) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        let mut entries = self.lock();
+        entries.retain(|q| q != query);
+        entries.insert(0, query.to_string());
+        entries.truncate(capacity);
+        self.save_in_background(&entries);
+    }
+
+    pub fn get(&self, index: usize) -> Option<String> {
+        self.lock().get(index).cloned()
+    }
+
+    // the first entry at or after `start` that contains the needle, for reverse incremental search
+    pub fn find(&self, needle: &str, start: usize) -> Option<(usize, String)> {
+        let needle = needle.to_lowercase();
+        self.lock()
+            .iter()
+            .enumerate()
+            .skip(start)
+            .find(|(_, q)| q.to_lowercase().contains(&needle))
+            .map(|(i, q)| (i, q.clone()))
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.clear();
+        self.save_in_background(&entries);
+    }
+}
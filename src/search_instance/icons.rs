@@ -0,0 +1,260 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use quick_search_lib::SearchResult;
+
+use super::WorkerPool;
+use crate::LOGGER;
+use quick_search_lib::Log;
+
+// largest edge of a decoded icon, anything bigger gets thumbnailed down before it is uploaded as a texture
+const ICON_TEXTURE_SIZE: u32 = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResultIcon {
+    // an encoded image, the key is used to cache the decoded texture
+    Bytes { key: String, bytes: Arc<[u8]> },
+    Path(PathBuf),
+    // one of the built-in glyph icons
+    Named(&'static str),
+}
+
+impl ResultIcon {
+    // the plugin abi has no icon field on results, so until quick-search-lib adds one this guess from the title and context is all there is
+    // svg isn't decoded, the image crate has no svg support and pulling in a rasterizer only for icons isn't worth it yet
+    fn guess(result: &SearchResult) -> Option<Self> {
+        for candidate in [result.context(), result.title()] {
+            if candidate.starts_with("http://") || candidate.starts_with("https://") {
+                return Some(ResultIcon::Named("link"));
+            }
+            let path = PathBuf::from(candidate);
+            if !path.is_absolute() {
+                continue;
+            }
+            if path.is_dir() {
+                return Some(ResultIcon::Named("folder"));
+            }
+            if path.is_file() {
+                let is_image = path
+                    .extension()
+                    .map(|e| matches!(e.to_string_lossy().to_lowercase().as_str(), "png" | "jpg" | "jpeg" | "bmp" | "gif" | "ico" | "webp"))
+                    .unwrap_or(false);
+                return Some(if is_image { ResultIcon::Path(path) } else { ResultIcon::Named("file") });
+            }
+        }
+        None
+    }
+
+    // the abi has no plugin icon either, so a png with the same name as the library next to it is used, e.g. plugins/files.dll and plugins/files.png
+    // this stays as the fallback for plugins that don't provide one once the abi does
+    pub fn for_plugin(library_path: &std::path::Path) -> Option<Self> {
+        let icon_path = library_path.with_extension("png");
+        if !icon_path.exists() {
+            return None;
+        }
+        match std::fs::read(&icon_path) {
+            Ok(bytes) => Some(ResultIcon::Bytes {
+                key: icon_path.to_string_lossy().into_owned(),
+                bytes: bytes.into(),
+            }),
+            Err(e) => {
+                LOGGER.error(&format!("Failed to read plugin icon {:?}: {}", icon_path, e));
+                None
+            }
+        }
+    }
+}
+
+fn texture_key(icon: &ResultIcon) -> String {
+    match icon {
+        ResultIcon::Bytes { key, .. } => key.clone(),
+        ResultIcon::Path(path) => path.to_string_lossy().into_owned(),
+        ResultIcon::Named(name) => name.to_string(),
+    }
+}
+
+fn decode(key: &str, icon: &ResultIcon, texture_size: u32) -> Option<egui::ColorImage> {
+    let decoded = match icon {
+        ResultIcon::Bytes { bytes, .. } => image::load_from_memory(bytes),
+        ResultIcon::Path(path) => image::open(path),
+        ResultIcon::Named(_) => return None,
+    };
+    match decoded {
+        Ok(img) => {
+            let img = img.thumbnail(texture_size, texture_size).to_rgba8();
+            let (width, height) = img.dimensions();
+            Some(egui::ColorImage::from_rgba_unmultiplied([width as usize, height as usize], img.as_raw()))
+        }
+        Err(e) => {
+            LOGGER.warn(&format!("Failed to decode icon {}: {}", key, e));
+            None
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(e) => e.into_inner(),
+    }
+}
+
+fn named_glyph(name: &str) -> &'static str {
+    match name {
+        "folder" => "🗀",
+        "file" => "🗋",
+        "link" => "🔗",
+        "search" => "🔍",
+        _ => "•",
+    }
+}
+
+// work waiting for the pool, drained by one job at a time so icons never fill the queue ahead of searches
+#[derive(Default)]
+struct IconRequests {
+    guesses: Vec<SearchResult>,
+    decodes: Vec<(String, ResultIcon)>,
+}
+
+enum IconLoaded {
+    Guess((String, String), Option<ResultIcon>),
+    Image(String, Option<egui::ColorImage>),
+}
+
+enum Loading<T> {
+    Pending,
+    Done(T),
+}
+
+// guessing touches the filesystem and decoding is slow, so both run on a worker pool and the row shows a placeholder until they are done
+// decoded textures are kept for the lifetime of the search window, failures are cached too so a broken image is only decoded once
+pub struct IconCache {
+    textures: HashMap<String, Loading<Option<egui::TextureHandle>>>,
+    // guessed icons by title and context, so the filesystem is only checked once per result instead of every frame
+    guesses: HashMap<(String, String), Loading<Option<ResultIcon>>>,
+    texture_size: u32,
+    pool: &'static WorkerPool,
+    pool_key: &'static str,
+    requests: Arc<Mutex<IconRequests>>,
+    sender: crossbeam::channel::Sender<IconLoaded>,
+    receiver: crossbeam::channel::Receiver<IconLoaded>,
+}
+
+impl Default for IconCache {
+    fn default() -> Self {
+        Self::new(&crate::SEARCH_POOL, "icons", ICON_TEXTURE_SIZE)
+    }
+}
+
+impl IconCache {
+    pub fn new(pool: &'static WorkerPool, pool_key: &'static str, texture_size: u32) -> Self {
+        let (sender, receiver) = crossbeam::channel::unbounded();
+        Self {
+            textures: HashMap::new(),
+            guesses: HashMap::new(),
+            texture_size,
+            pool,
+            pool_key,
+            requests: Arc::default(),
+            sender,
+            receiver,
+        }
+    }
+
+    // None while the guess is still running
+    pub fn for_result(&mut self, ctx: &egui::Context, result: &SearchResult) -> Option<ResultIcon> {
+        self.receive(ctx);
+        let key = (result.title().to_string(), result.context().to_string());
+        match self.guesses.get(&key) {
+            Some(Loading::Done(icon)) => icon.clone(),
+            Some(Loading::Pending) => None,
+            None => {
+                self.guesses.insert(key, Loading::Pending);
+                self.request(ctx, |requests| requests.guesses.push(result.clone()));
+                None
+            }
+        }
+    }
+
+    fn receive(&mut self, ctx: &egui::Context) {
+        while let Ok(loaded) = self.receiver.try_recv() {
+            match loaded {
+                IconLoaded::Guess(key, icon) => {
+                    self.guesses.insert(key, Loading::Done(icon));
+                }
+                IconLoaded::Image(key, image) => {
+                    let texture = image.map(|image| ctx.load_texture(key.clone(), image, egui::TextureOptions::LINEAR));
+                    self.textures.insert(key, Loading::Done(texture));
+                }
+            }
+        }
+    }
+
+    fn request(&self, ctx: &egui::Context, add: impl FnOnce(&mut IconRequests)) {
+        add(&mut lock(&self.requests));
+        let requests = Arc::clone(&self.requests);
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        let texture_size = self.texture_size;
+        // a job that is still queued is replaced by this one, and whichever runs drains everything asked for so far
+        self.pool.submit(self.pool_key, move || loop {
+            let IconRequests { guesses, decodes } = std::mem::take(&mut *lock(&requests));
+            if guesses.is_empty() && decodes.is_empty() {
+                return;
+            }
+            for result in guesses {
+                let icon = ResultIcon::guess(&result);
+                let _ = sender.send(IconLoaded::Guess((result.title().to_string(), result.context().to_string()), icon));
+            }
+            for (key, icon) in decodes {
+                let image = decode(&key, &icon, texture_size);
+                let _ = sender.send(IconLoaded::Image(key, image));
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    // None while the image is still being decoded or if it failed to decode
+    fn texture(&mut self, ctx: &egui::Context, icon: &ResultIcon) -> Option<egui::TextureHandle> {
+        self.receive(ctx);
+        if let ResultIcon::Named(_) = icon {
+            return None;
+        }
+        let key = texture_key(icon);
+        match self.textures.get(&key) {
+            Some(Loading::Done(texture)) => texture.clone(),
+            Some(Loading::Pending) => None,
+            None => {
+                self.textures.insert(key.clone(), Loading::Pending);
+                self.request(ctx, |requests| requests.decodes.push((key, icon.clone())));
+                None
+            }
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, icon: &ResultIcon, size: f32) {
+        match icon {
+            ResultIcon::Named(name) => {
+                ui.add(egui::Label::new(egui::RichText::new(named_glyph(name)).size(size)).wrap(false));
+            }
+            _ => match self.texture(ui.ctx(), icon) {
+                Some(texture) => {
+                    // fit the longest edge to the requested size so thumbnails keep their aspect ratio
+                    let texture_size = texture.size_vec2();
+                    let scale = size / texture_size.max_elem().max(1.0);
+                    ui.add(egui::Image::from_texture(egui::load::SizedTexture::new(texture.id(), texture_size * scale)));
+                }
+                None => {
+                    // keep the rows lined up while the icon loads or if it failed to load
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+                    if matches!(self.textures.get(&texture_key(icon)), Some(Loading::Pending)) {
+                        ui.painter().rect_filled(rect, 2.0, ui.visuals().faint_bg_color);
+                    }
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use std::collections::BTreeMap;
+
+use egui::Key;
+use serde::{Deserialize, Serialize};
+
+use crate::LOGGER;
+use quick_search_lib::Log;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    Next,
+    Prev,
+    NextGroup,
+    PrevGroup,
+    // switch between typing in the search bar and moving through the results
+    ToggleFocus,
+    Execute,
+    // the action middle-clicking a result runs
+    AltAction,
+    ActionMenu,
+    Close,
+    ClearInput,
+    HistoryPrev,
+    HistoryNext,
+    HistorySearch,
+    Mark,
+    ShowMore,
+    Collapse,
+    Expand,
+    // show only the group under the cursor
+    FocusGroup,
+}
+
+impl KeyAction {
+    pub const ALL: [KeyAction; 18] = [
+        KeyAction::Next,
+        KeyAction::Prev,
+        KeyAction::NextGroup,
+        KeyAction::PrevGroup,
+        KeyAction::ToggleFocus,
+        KeyAction::Execute,
+        KeyAction::AltAction,
+        KeyAction::ActionMenu,
+        KeyAction::Close,
+        KeyAction::ClearInput,
+        KeyAction::HistoryPrev,
+        KeyAction::HistoryNext,
+        KeyAction::HistorySearch,
+        KeyAction::Mark,
+        KeyAction::ShowMore,
+        KeyAction::Collapse,
+        KeyAction::Expand,
+        KeyAction::FocusGroup,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            KeyAction::Next => "Move to the next result, pressing it twice in the search bar moves into the results",
+            KeyAction::Prev => "Move to the previous result, pressing it twice in the search bar moves into the results from the bottom",
+            KeyAction::NextGroup => "Jump to the next plugin's results",
+            KeyAction::PrevGroup => "Jump to the previous plugin's results",
+            KeyAction::ToggleFocus => "Switch between typing in the search bar and moving through the results",
+            KeyAction::Execute => "Open the marked results, or the one under the cursor. Pressing Enter in the search bar moves into the results instead.",
+            KeyAction::AltAction => "Show the result under the cursor in its folder if it is a file, otherwise copy its title",
+            KeyAction::ActionMenu => "Open the actions menu for the result under the cursor",
+            KeyAction::Close => "Close menus and focused groups, then the search bar",
+            KeyAction::ClearInput => "Clear the search bar",
+            KeyAction::HistoryPrev => "Recall the previous submitted query",
+            KeyAction::HistoryNext => "Recall the next submitted query",
+            KeyAction::HistorySearch => "Search the submitted queries, pressing it again finds the next match",
+            KeyAction::Mark => "Mark the result under the cursor, marked results are opened together",
+            KeyAction::ShowMore => "Show more results from the plugin under the cursor",
+            KeyAction::Collapse => "Collapse the plugin under the cursor",
+            KeyAction::Expand => "Expand the next collapsed plugin",
+            KeyAction::FocusGroup => "Show only the plugin under the cursor, with all of its results",
+        }
+    }
+}
+
+impl std::fmt::Display for KeyAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyAction::Next => write!(f, "Next result"),
+            KeyAction::Prev => write!(f, "Previous result"),
+            KeyAction::NextGroup => write!(f, "Next group"),
+            KeyAction::PrevGroup => write!(f, "Previous group"),
+            KeyAction::ToggleFocus => write!(f, "Toggle search bar"),
+            KeyAction::Execute => write!(f, "Open"),
+            KeyAction::AltAction => write!(f, "Secondary action"),
+            KeyAction::ActionMenu => write!(f, "Actions menu"),
+            KeyAction::Close => write!(f, "Close"),
+            KeyAction::ClearInput => write!(f, "Clear search bar"),
+            KeyAction::HistoryPrev => write!(f, "Previous query"),
+            KeyAction::HistoryNext => write!(f, "Next query"),
+            KeyAction::HistorySearch => write!(f, "Search history"),
+            KeyAction::Mark => write!(f, "Mark result"),
+            KeyAction::ShowMore => write!(f, "Show more"),
+            KeyAction::Collapse => write!(f, "Collapse group"),
+            KeyAction::Expand => write!(f, "Expand group"),
+            KeyAction::FocusGroup => write!(f, "Focus group"),
+        }
+    }
+}
+
+// a key and the modifiers held with it, written like "Ctrl+Shift+Enter" in config.toml
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyChord {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub key: Key,
+}
+
+impl KeyChord {
+    pub fn new(key: Key) -> Self {
+        KeyChord {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            key,
+        }
+    }
+
+    pub fn ctrl(key: Key) -> Self {
+        KeyChord { ctrl: true, ..KeyChord::new(key) }
+    }
+
+    pub fn alt(key: Key) -> Self {
+        KeyChord { alt: true, ..KeyChord::new(key) }
+    }
+
+    pub fn shift(key: Key) -> Self {
+        KeyChord { shift: true, ..KeyChord::new(key) }
+    }
+
+    pub fn from_event(key: Key, modifiers: egui::Modifiers) -> Self {
+        KeyChord {
+            ctrl: modifiers.ctrl,
+            alt: modifiers.alt,
+            shift: modifiers.shift,
+            key,
+        }
+    }
+
+    // modifiers have to match exactly, so down doesn't also fire on ctrl+down
+    fn matches(&self, key: Key, modifiers: egui::Modifiers) -> bool {
+        self.key == key && self.ctrl == modifiers.ctrl && self.alt == modifiers.alt && self.shift == modifiers.shift
+    }
+
+    // chords that would type a character into the search bar, these only work while moving through the results
+    fn types_text(&self) -> bool {
+        // egui orders its keys so the punctuation, digits and letters sit together between colon and z
+        !self.ctrl && !self.alt && (self.key == Key::Space || (Key::Colon..=Key::Z).contains(&self.key))
+    }
+}
+
+impl std::fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", self.key.name())
+    }
+}
+
+impl From<KeyChord> for String {
+    fn from(chord: KeyChord) -> Self {
+        chord.to_string()
+    }
+}
+
+impl TryFrom<String> for KeyChord {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut parts: Vec<&str> = value.split('+').map(|p| p.trim()).collect();
+        let key = parts.pop().and_then(Key::from_name).ok_or_else(|| format!("unknown key in {:?}", value))?;
+        let mut chord = KeyChord::new(key);
+        for part in parts {
+            match part.to_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "alt" => chord.alt = true,
+                "shift" => chord.shift = true,
+                _ => return Err(format!("unknown modifier {:?} in {:?}", part, value)),
+            }
+        }
+        Ok(chord)
+    }
+}
+
+// every action with the chords that trigger it, an action with no chords can only be reached with the mouse
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(transparent)]
+pub struct Keymap(BTreeMap<KeyAction, Vec<KeyChord>>);
+
+// a typo in one chord shouldn't throw away the whole config, so actions and chords that don't parse are skipped with a warning
+// and an action with a bad chord keeps its default chords
+impl<'de> Deserialize<'de> for Keymap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = BTreeMap::<String, Vec<String>>::deserialize(deserializer)?;
+        let mut keymap = BTreeMap::new();
+        for (name, chords) in raw {
+            let action = match KeyAction::deserialize(serde::de::value::StrDeserializer::<serde::de::value::Error>::new(&name)) {
+                Ok(action) => action,
+                Err(_) => {
+                    LOGGER.warn(&format!("ignoring unknown key action {:?} in the keymap", name));
+                    continue;
+                }
+            };
+            match chords.into_iter().map(KeyChord::try_from).collect::<Result<Vec<_>, _>>() {
+                Ok(chords) => {
+                    keymap.insert(action, chords);
+                }
+                Err(e) => LOGGER.warn(&format!("keeping the default keys for {}, {}", action, e)),
+            }
+        }
+        Ok(Keymap(keymap))
+    }
+}
+
+impl Default for Keymap {
+    // the keys the overlay always used
+    fn default() -> Self {
+        Keymap(
+            [
+                (KeyAction::Next, vec![KeyChord::new(Key::ArrowDown)]),
+                (KeyAction::Prev, vec![KeyChord::new(Key::ArrowUp)]),
+                (KeyAction::NextGroup, vec![KeyChord::new(Key::PageDown)]),
+                (KeyAction::PrevGroup, vec![KeyChord::new(Key::PageUp)]),
+                (KeyAction::ToggleFocus, vec![KeyChord::new(Key::Tab)]),
+                (KeyAction::Execute, vec![KeyChord::new(Key::Enter)]),
+                (KeyAction::AltAction, vec![KeyChord::alt(Key::Enter)]),
+                (KeyAction::ActionMenu, vec![KeyChord::shift(Key::Enter)]),
+                (KeyAction::Close, vec![KeyChord::new(Key::Escape)]),
+                (KeyAction::ClearInput, vec![KeyChord::shift(Key::Escape)]),
+                (KeyAction::HistoryPrev, vec![KeyChord::alt(Key::ArrowUp)]),
+                (KeyAction::HistoryNext, vec![KeyChord::alt(Key::ArrowDown)]),
+                (KeyAction::HistorySearch, vec![KeyChord::ctrl(Key::R)]),
+                (KeyAction::Mark, vec![KeyChord::ctrl(Key::Space)]),
+                (KeyAction::ShowMore, vec![KeyChord::ctrl(Key::ArrowDown)]),
+                (KeyAction::Collapse, vec![KeyChord::ctrl(Key::ArrowLeft)]),
+                (KeyAction::Expand, vec![KeyChord::ctrl(Key::ArrowRight)]),
+                (KeyAction::FocusGroup, vec![KeyChord::ctrl(Key::F)]),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+}
+
+impl Keymap {
+    // the default keys with emacs' movement and cancel keys added
+    pub fn emacs() -> Self {
+        let mut keymap = Keymap::default();
+        keymap.add(KeyAction::Next, KeyChord::ctrl(Key::N));
+        keymap.add(KeyAction::Prev, KeyChord::ctrl(Key::P));
+        // meta-} and meta-{, ctrl+v would also paste
+        keymap.add(
+            KeyAction::NextGroup,
+            KeyChord {
+                alt: true,
+                ..KeyChord::shift(Key::CloseBracket)
+            },
+        );
+        keymap.add(
+            KeyAction::PrevGroup,
+            KeyChord {
+                alt: true,
+                ..KeyChord::shift(Key::OpenBracket)
+            },
+        );
+        keymap.add(KeyAction::Execute, KeyChord::ctrl(Key::J));
+        keymap.add(KeyAction::Close, KeyChord::ctrl(Key::G));
+        keymap.add(KeyAction::ClearInput, KeyChord::ctrl(Key::K));
+        keymap.add(KeyAction::HistoryPrev, KeyChord::alt(Key::P));
+        keymap.add(KeyAction::HistoryNext, KeyChord::alt(Key::N));
+        keymap
+    }
+
+    // the default keys with vim's, the plain letters only work while moving through the results
+    pub fn vim() -> Self {
+        let mut keymap = Keymap::default();
+        keymap.add(KeyAction::Next, KeyChord::new(Key::J));
+        keymap.add(KeyAction::Next, KeyChord::ctrl(Key::J));
+        keymap.add(KeyAction::Prev, KeyChord::new(Key::K));
+        keymap.add(KeyAction::Prev, KeyChord::ctrl(Key::K));
+        keymap.add(KeyAction::NextGroup, KeyChord::ctrl(Key::D));
+        keymap.add(KeyAction::PrevGroup, KeyChord::ctrl(Key::U));
+        keymap.add(KeyAction::ToggleFocus, KeyChord::new(Key::I));
+        keymap.add(KeyAction::Execute, KeyChord::new(Key::O));
+        keymap.add(KeyAction::ActionMenu, KeyChord::new(Key::A));
+        keymap.add(KeyAction::Close, KeyChord::new(Key::Q));
+        keymap.add(KeyAction::ClearInput, KeyChord::ctrl(Key::W));
+        keymap.add(KeyAction::HistoryPrev, KeyChord::ctrl(Key::P));
+        keymap.add(KeyAction::HistoryNext, KeyChord::ctrl(Key::N));
+        keymap.add(KeyAction::Mark, KeyChord::new(Key::Space));
+        keymap.add(KeyAction::Collapse, KeyChord::new(Key::H));
+        keymap.add(KeyAction::Expand, KeyChord::new(Key::L));
+        keymap
+    }
+
+    pub fn presets() -> [(&'static str, Keymap); 3] {
+        [("Default", Keymap::default()), ("Emacs", Keymap::emacs()), ("Vim", Keymap::vim())]
+    }
+
+    // actions missing from config.toml get their default chords, so new actions work without editing it
+    pub fn filled(mut self) -> Self {
+        for (action, chords) in Keymap::default().0 {
+            self.0.entry(action).or_insert(chords);
+        }
+        self
+    }
+
+    pub fn chords(&self, action: KeyAction) -> &[KeyChord] {
+        self.0.get(&action).map(|c| c.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn add(&mut self, action: KeyAction, chord: KeyChord) {
+        let chords = self.0.entry(action).or_default();
+        if !chords.contains(&chord) {
+            chords.push(chord);
+        }
+    }
+
+    pub fn remove(&mut self, action: KeyAction, chord: KeyChord) {
+        if let Some(chords) = self.0.get_mut(&action) {
+            chords.retain(|c| *c != chord);
+        }
+    }
+
+    pub fn reset(&mut self, action: KeyAction) {
+        self.0.insert(action, Keymap::default().chords(action).to_vec());
+    }
+
+    // chords bound to more than one action, pressing one only triggers whichever action is checked first
+    pub fn conflicts(&self) -> Vec<(KeyChord, Vec<KeyAction>)> {
+        let mut conflicts: Vec<(KeyChord, Vec<KeyAction>)> = Vec::new();
+        for (action, chords) in self.0.iter() {
+            for chord in chords {
+                match conflicts.iter_mut().find(|(c, _)| c == chord) {
+                    Some((_, actions)) => actions.push(*action),
+                    None => conflicts.push((*chord, vec![*action])),
+                }
+            }
+        }
+        conflicts.retain(|(_, actions)| actions.len() > 1);
+        conflicts
+    }
+
+    // how an action's chords are shown in hints
+    pub fn describe(&self, action: KeyAction) -> String {
+        match self.chords(action) {
+            [] => "unbound".to_string(),
+            chords => chords.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" or "),
+        }
+    }
+
+    // consumes a press of one of the action's chords, chords that type text are skipped while typing in the search bar
+    pub fn consume(&self, input: &mut egui::InputState, action: KeyAction, typing: bool) -> bool {
+        let chords = self.chords(action);
+        let mut found = false;
+        input.events.retain(|event| match event {
+            egui::Event::Key { key, modifiers, pressed: true, .. } if !found && chords.iter().any(|c| c.matches(*key, *modifiers) && !(typing && c.types_text())) => {
+                found = true;
+                false
+            }
+            _ => true,
+        });
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chords_round_trip() {
+        for text in ["Enter", "Ctrl+R", "Alt+Shift+Up", "Ctrl+Alt+Shift+Space"] {
+            let chord = KeyChord::try_from(text.to_string()).unwrap();
+            assert_eq!(chord.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn modifiers_are_case_insensitive() {
+        assert_eq!(
+            KeyChord::try_from("control+shift+Enter".to_string()),
+            Ok(KeyChord {
+                ctrl: true,
+                ..KeyChord::shift(Key::Enter)
+            })
+        );
+    }
+
+    #[test]
+    fn bad_chords_are_errors() {
+        assert!(KeyChord::try_from("Ctl+K".to_string()).is_err());
+        assert!(KeyChord::try_from("Ctrl+Nope".to_string()).is_err());
+        assert!(KeyChord::try_from(String::new()).is_err());
+    }
+
+    #[test]
+    fn letters_only_type_without_ctrl_or_alt() {
+        assert!(KeyChord::new(Key::J).types_text());
+        assert!(KeyChord::shift(Key::J).types_text());
+        assert!(KeyChord::new(Key::Space).types_text());
+        assert!(!KeyChord::ctrl(Key::J).types_text());
+        assert!(!KeyChord::new(Key::ArrowDown).types_text());
+    }
+
+    #[test]
+    fn a_bad_chord_keeps_that_actions_defaults() {
+        let keymap = toml::from_str::<Keymap>(
+            r#"
+            next = ["Ctl+J"]
+            prev = ["Ctrl+K"]
+            not_an_action = ["Enter"]
+            "#,
+        )
+        .unwrap()
+        .filled();
+        assert_eq!(keymap.chords(KeyAction::Next), Keymap::default().chords(KeyAction::Next));
+        assert_eq!(keymap.chords(KeyAction::Prev), &[KeyChord::ctrl(Key::K)]);
+        assert_eq!(keymap.chords(KeyAction::Close), Keymap::default().chords(KeyAction::Close));
+    }
+
+    #[test]
+    fn keymaps_round_trip_through_toml() {
+        for (_, keymap) in Keymap::presets() {
+            let text = toml::to_string(&keymap).unwrap();
+            assert_eq!(toml::from_str::<Keymap>(&text).unwrap(), keymap);
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+// how many of the most recent search latencies are kept per plugin for the percentile calculations
+const LATENCY_SAMPLES: usize = 256;
+// gaps between keystrokes longer than this are pauses, not typing
+const MAX_KEYSTROKE_GAP: Duration = Duration::from_millis(1000);
+// assumed typing cadence until a few keystrokes have been seen
+const DEFAULT_KEYSTROKE_MS: f64 = 200.0;
+
+#[derive(Default)]
+pub struct MetricsStore {
+    plugins: Mutex<HashMap<String, PluginMetrics>>,
+    // moving average of the time between keystrokes in ms
+    keystroke_ms: Mutex<Option<f64>>,
+    // searches that haven't answered yet, so a timeout is counted when the deadline passes instead of whenever the search returns
+    running: Mutex<HashMap<u64, RunningSearch>>,
+    next_search: AtomicU64,
+}
+
+struct RunningSearch {
+    name: String,
+    deadline: Instant,
+    timed_out: bool,
+}
+
+#[derive(Default, Clone)]
+struct PluginMetrics {
+    searches: u64,
+    panics: u64,
+    timeouts: u64,
+    total_results: u64,
+    latencies: VecDeque<Duration>,
+    // worked out whenever a latency is recorded, so reading them every frame doesn't sort anything
+    p50_ms: f64,
+    p95_ms: f64,
+    executions: u64,
+    execute_time: Duration,
+}
+
+impl MetricsStore {
+    fn with_plugin(&self, name: &str, f: impl FnOnce(&mut PluginMetrics)) {
+        let mut plugins = match self.plugins.lock() {
+            Ok(plugins) => plugins,
+            Err(e) => e.into_inner(),
+        };
+        f(plugins.entry(name.to_string()).or_default());
+    }
+
+    // returns the id the search reports back with
+    pub fn start_search(&self, name: &str, timeout: Duration) -> u64 {
+        let id = self.next_search.fetch_add(1, Ordering::Relaxed);
+        self.lock_running().insert(
+            id,
+            RunningSearch {
+                name: name.to_string(),
+                deadline: Instant::now() + timeout,
+                timed_out: false,
+            },
+        );
+        id
+    }
+
+    // counts every search whose deadline has passed since the last check, each one only once
+    pub fn check_deadlines(&self) {
+        let now = Instant::now();
+        let mut expired = Vec::new();
+        for search in self.lock_running().values_mut().filter(|search| !search.timed_out && search.deadline <= now) {
+            search.timed_out = true;
+            expired.push(search.name.clone());
+        }
+        for name in expired {
+            self.with_plugin(&name, |m| m.timeouts += 1);
+        }
+    }
+
+    pub fn record_search(&self, id: u64, name: &str, elapsed: Duration, num_results: usize) {
+        self.finish_search(id);
+        self.with_plugin(name, |m| {
+            m.searches += 1;
+            m.total_results += num_results as u64;
+            m.push_latency(elapsed);
+        });
+    }
+
+    pub fn record_panic(&self, id: u64, name: &str, elapsed: Duration) {
+        self.finish_search(id);
+        self.with_plugin(name, |m| {
+            m.searches += 1;
+            m.panics += 1;
+            m.push_latency(elapsed);
+        });
+    }
+
+    // a search that answers late still counts as a timeout if nothing checked the deadline in the meantime
+    fn finish_search(&self, id: u64) {
+        self.check_deadlines();
+        self.lock_running().remove(&id);
+    }
+
+    fn lock_running(&self) -> std::sync::MutexGuard<'_, HashMap<u64, RunningSearch>> {
+        match self.running.lock() {
+            Ok(running) => running,
+            Err(e) => e.into_inner(),
+        }
+    }
+
+    pub fn record_execute(&self, name: &str, elapsed: Duration) {
+        self.with_plugin(name, |m| {
+            m.executions += 1;
+            m.execute_time += elapsed;
+        });
+    }
+
+    pub fn record_keystroke(&self, gap: Duration) {
+        if gap > MAX_KEYSTROKE_GAP {
+            return;
+        }
+        let mut keystroke_ms = match self.keystroke_ms.lock() {
+            Ok(keystroke_ms) => keystroke_ms,
+            Err(e) => e.into_inner(),
+        };
+        let gap = gap.as_secs_f64() * 1000.0;
+        *keystroke_ms = Some(match *keystroke_ms {
+            Some(average) => average * 0.7 + gap * 0.3,
+            None => gap,
+        });
+    }
+
+    pub fn keystroke_ms(&self) -> f64 {
+        let keystroke_ms = match self.keystroke_ms.lock() {
+            Ok(keystroke_ms) => keystroke_ms,
+            Err(e) => e.into_inner(),
+        };
+        keystroke_ms.unwrap_or(DEFAULT_KEYSTROKE_MS)
+    }
+
+    // a plugin that answers before the next keystroke can search on every keystroke,
+    // a slower one waits for a pause in typing so it isn't kept busy with queries that are already outdated
+    pub fn auto_delay(&self, name: &str, min: u32, max: u32) -> Option<u32> {
+        let p50 = {
+            let plugins = match self.plugins.lock() {
+                Ok(plugins) => plugins,
+                Err(e) => e.into_inner(),
+            };
+            let metrics = plugins.get(name)?;
+            if metrics.latencies.is_empty() {
+                return None;
+            }
+            metrics.p50_ms
+        };
+        let delay = p50.min(self.keystroke_ms() * 1.5);
+        Some((delay.round() as u32).clamp(min, max.max(min)))
+    }
+
+    pub fn snapshot(&self) -> Vec<PluginMetricsSnapshot> {
+        self.check_deadlines();
+        let plugins = match self.plugins.lock() {
+            Ok(plugins) => plugins,
+            Err(e) => e.into_inner(),
+        };
+        plugins.iter().map(|(name, m)| m.snapshot(name)).collect()
+    }
+}
+
+impl PluginMetrics {
+    fn push_latency(&mut self, elapsed: Duration) {
+        if self.latencies.len() >= LATENCY_SAMPLES {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(elapsed);
+        let mut sorted = self.latencies.iter().copied().collect::<Vec<_>>();
+        sorted.sort();
+        self.p50_ms = percentile(&sorted, 0.5);
+        self.p95_ms = percentile(&sorted, 0.95);
+    }
+
+    fn snapshot(&self, name: &str) -> PluginMetricsSnapshot {
+        let successful = self.searches.saturating_sub(self.panics);
+        PluginMetricsSnapshot {
+            name: name.to_string(),
+            searches: self.searches,
+            p50_ms: self.p50_ms,
+            p95_ms: self.p95_ms,
+            panics: self.panics,
+            timeouts: self.timeouts,
+            average_results: if successful == 0 { 0.0 } else { self.total_results as f64 / successful as f64 },
+            executions: self.executions,
+            execute_ms: self.execute_time.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+// nearest-sample percentile of latencies sorted in ascending order, in ms
+fn percentile(sorted: &[Duration], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index].as_secs_f64() * 1000.0
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PluginMetricsSnapshot {
+    pub name: String,
+    pub searches: u64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub panics: u64,
+    pub timeouts: u64,
+    pub average_results: f64,
+    pub executions: u64,
+    pub execute_ms: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsColumn {
+    Name,
+    Searches,
+    P50,
+    P95,
+    Panics,
+    Timeouts,
+    AverageResults,
+    ExecuteTime,
+}
+
+impl MetricsColumn {
+    pub const ALL: [MetricsColumn; 8] = [
+        MetricsColumn::Name,
+        MetricsColumn::Searches,
+        MetricsColumn::P50,
+        MetricsColumn::P95,
+        MetricsColumn::Panics,
+        MetricsColumn::Timeouts,
+        MetricsColumn::AverageResults,
+        MetricsColumn::ExecuteTime,
+    ];
+
+    pub fn hover_text(&self) -> &'static str {
+        match self {
+            MetricsColumn::Name => "The name of the plugin",
+            MetricsColumn::Searches => "How many searches the plugin has run",
+            MetricsColumn::P50 => "Median search latency in ms",
+            MetricsColumn::P95 => "95th percentile search latency in ms",
+            MetricsColumn::Panics => "How many searches panicked on the QuickSearch side of the call, e.g. while converting results. Only these are counted: a panic inside the plugin itself can't be caught and closes QuickSearch",
+            MetricsColumn::Timeouts => "How many searches took longer than the search timeout",
+            MetricsColumn::AverageResults => "Average number of results per successful search",
+            MetricsColumn::ExecuteTime => "Total time spent executing results in ms, and how many results were executed",
+        }
+    }
+
+    pub fn sort(&self, rows: &mut [PluginMetricsSnapshot]) {
+        match self {
+            MetricsColumn::Name => rows.sort_by(|a, b| a.name.cmp(&b.name)),
+            MetricsColumn::Searches => rows.sort_by_key(|row| row.searches),
+            MetricsColumn::P50 => rows.sort_by(|a, b| a.p50_ms.total_cmp(&b.p50_ms)),
+            MetricsColumn::P95 => rows.sort_by(|a, b| a.p95_ms.total_cmp(&b.p95_ms)),
+            MetricsColumn::Panics => rows.sort_by_key(|row| row.panics),
+            MetricsColumn::Timeouts => rows.sort_by_key(|row| row.timeouts),
+            MetricsColumn::AverageResults => rows.sort_by(|a, b| a.average_results.total_cmp(&b.average_results)),
+            MetricsColumn::ExecuteTime => rows.sort_by(|a, b| a.execute_ms.total_cmp(&b.execute_ms)),
+        }
+    }
+
+    pub fn cell(&self, row: &PluginMetricsSnapshot) -> String {
+        match self {
+            MetricsColumn::Name => row.name.clone(),
+            MetricsColumn::Searches => row.searches.to_string(),
+            MetricsColumn::P50 => format!("{:.1}", row.p50_ms),
+            MetricsColumn::P95 => format!("{:.1}", row.p95_ms),
+            MetricsColumn::Panics => row.panics.to_string(),
+            MetricsColumn::Timeouts => row.timeouts.to_string(),
+            MetricsColumn::AverageResults => format!("{:.1}", row.average_results),
+            MetricsColumn::ExecuteTime => format!("{:.1} ({})", row.execute_ms, row.executions),
+        }
+    }
+}
+
+impl std::fmt::Display for MetricsColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetricsColumn::Name => write!(f, "Plugin"),
+            MetricsColumn::Searches => write!(f, "Searches"),
+            MetricsColumn::P50 => write!(f, "p50"),
+            MetricsColumn::P95 => write!(f, "p95"),
+            MetricsColumn::Panics => write!(f, "Host panics"),
+            MetricsColumn::Timeouts => write!(f, "Timeouts"),
+            MetricsColumn::AverageResults => write!(f, "Avg results"),
+            MetricsColumn::ExecuteTime => write!(f, "Execute"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_pick_the_nearest_sample() {
+        let mut metrics = PluginMetrics::default();
+        assert_eq!(metrics.p50_ms, 0.0);
+        for ms in 1..=100 {
+            metrics.push_latency(Duration::from_millis(ms));
+        }
+        assert_eq!(metrics.p50_ms, 51.0);
+        assert_eq!(metrics.p95_ms, 95.0);
+        assert_eq!(percentile(&[Duration::from_millis(1), Duration::from_millis(100)], 1.0), 100.0);
+    }
+
+    #[test]
+    fn only_the_latest_samples_are_kept() {
+        let mut metrics = PluginMetrics::default();
+        for _ in 0..LATENCY_SAMPLES {
+            metrics.push_latency(Duration::from_millis(1));
+        }
+        for _ in 0..LATENCY_SAMPLES / 2 + 1 {
+            metrics.push_latency(Duration::from_millis(500));
+        }
+        assert_eq!(metrics.latencies.len(), LATENCY_SAMPLES);
+        assert_eq!(metrics.p50_ms, 500.0);
+    }
+
+    fn store_with_latency(name: &str, latency: Duration) -> MetricsStore {
+        let store = MetricsStore::default();
+        let id = store.start_search(name, Duration::from_secs(60));
+        store.record_search(id, name, latency, 1);
+        store
+    }
+
+    #[test]
+    fn auto_delay_is_clamped() {
+        assert_eq!(MetricsStore::default().auto_delay("files", 0, 1000), None);
+        // a slow plugin waits for a pause in typing, capped by the keystroke cadence and then by the limits
+        let slow = store_with_latency("files", Duration::from_secs(5));
+        assert_eq!(slow.auto_delay("files", 0, 10000), Some((DEFAULT_KEYSTROKE_MS * 1.5) as u32));
+        assert_eq!(slow.auto_delay("files", 0, 100), Some(100));
+        // a maximum below the minimum gives way to the minimum
+        assert_eq!(slow.auto_delay("files", 500, 100), Some(500));
+        let fast = store_with_latency("files", Duration::from_millis(20));
+        assert_eq!(fast.auto_delay("files", 50, 1000), Some(50));
+    }
+
+    #[test]
+    fn timeouts_are_counted_once_at_the_deadline() {
+        let store = MetricsStore::default();
+        let id = store.start_search("files", Duration::ZERO);
+        store.check_deadlines();
+        store.check_deadlines();
+        assert_eq!(store.snapshot()[0].timeouts, 1);
+        store.record_search(id, "files", Duration::from_millis(10), 0);
+        let snapshot = store.snapshot();
+        assert_eq!(snapshot[0].timeouts, 1);
+        assert_eq!(snapshot[0].searches, 1);
+    }
+}
@@ -1,13 +1,35 @@
+mod actions;
 mod app;
+mod cache;
 mod config;
+mod fonts;
+mod frecency;
+mod history;
+mod icons;
+mod keymap;
+mod metrics;
+mod placement;
+mod pool;
+mod preview;
+mod query;
+mod theme;
+use std::collections::HashSet;
 use std::sync::Arc;
-use std::{collections::HashSet, thread::JoinHandle};
 
 use crate::LOGGER;
 use quick_search_lib::Log;
 
 use quick_search_lib::{ColoredChar, Searchable_TO};
 
+pub use cache::ResultCache;
+pub use fonts::{FontLoader, FontSettings};
+pub use frecency::UsageStore;
+pub use history::QueryHistory;
+pub use keymap::{KeyAction, KeyChord, Keymap};
+pub use metrics::MetricsStore;
+pub use pool::{WorkerPool, DEFAULT_WORKER_THREADS};
+pub use theme::{Theme, ThemeBase};
+
 pub fn instance(search_bar: bool) {
     let plugins = load_plugins();
 
@@ -167,6 +189,7 @@ fn load_plugins() -> PluginLoadResult {
                                         colored_name: colored_char_to_layout_job(colored_name.into()),
                                         priority: plugin_info.priority,
                                         id: id.clone(),
+                                        icon: icons::ResultIcon::for_plugin(path.as_path()),
                                         // path,
                                         _p: Arc::new(plogon),
                                         _l: library,
@@ -220,6 +243,7 @@ pub struct Plugin {
     priority: u32,
     // delay: u32,
     id: quick_search_lib::PluginId,
+    icon: Option<icons::ResultIcon>,
     // path: std::path::PathBuf,
     _p: Arc<Searchable_TO<'static, quick_search_lib::abi_stable::std_types::RBox<()>>>,
     _l: quick_search_lib::SearchLib_Ref,
@@ -230,25 +254,126 @@ impl Plugin {
     //     self._p.search(query.into()).into()
     // }
     fn execute(&self, result: &quick_search_lib::SearchResult) {
+        let start = std::time::Instant::now();
         self._p.execute(result);
+        crate::PLUGIN_METRICS.record_execute(self.name, start.elapsed());
     }
-    fn search_delayed(&self, query: &str) -> JoinHandle<(Vec<quick_search_lib::SearchResult>, SearchMetadata)> {
+    // the abi has no batch execute, so marked results are handed over as jobs that run one after another on a single thread
+    // a panic inside the plugin can't unwind across the abi and takes the process down, only panics on the host side of the call are caught here
+    fn execute_job(&self, result: &quick_search_lib::SearchResult) -> ExecuteJob {
         let p = Arc::clone(&self._p);
-        let query = query.to_string();
-
-        let mut metadata = SearchMetadata {
+        let result = result.clone();
+        let name = self.name;
+        Box::new(move || {
+            let start = std::time::Instant::now();
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| p.execute(&result)));
+            crate::PLUGIN_METRICS.record_execute(name, start.elapsed());
+            if let Err(e) = res {
+                LOGGER.error(&format!("execute panicked for {}: {}", name, panic_message(e.as_ref())));
+            }
+        })
+    }
+    fn metadata(&self) -> SearchMetadata {
+        SearchMetadata {
             pretty_name: self.colored_name.clone(),
             priority: self.priority,
             raw_name: self.name.to_string(),
             id: self.id.clone(),
-            num_results: 0,
-        };
+            icon: self.icon.clone(),
+        }
+    }
+    fn search_pooled(&self, query: &query::SearchQuery, timeout: std::time::Duration, sink: SearchSink) {
+        let p = Arc::clone(&self._p);
+        // the plugin abi only takes a string, so phrases lose their quotes and the host checks them on the results instead
+        let query = query.text();
+        let name = self.name;
 
-        std::thread::spawn(move || {
-            let res: Vec<quick_search_lib::SearchResult> = p.search(query.into()).into();
-            metadata.num_results = res.len();
-            (res, metadata)
-        })
+        crate::SEARCH_POOL.submit(name, move || {
+            let start = std::time::Instant::now();
+            let id = crate::PLUGIN_METRICS.start_search(name, timeout);
+            // the plugin abi hands back every result at once, so each search sends exactly one message
+            // a panic inside the plugin can't unwind across the abi and aborts instead, so this only catches panics on the host side, like converting the results
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| -> Vec<quick_search_lib::SearchResult> { p.search(query.into()).into() }));
+            let elapsed = start.elapsed();
+            match res {
+                Ok(res) => {
+                    crate::PLUGIN_METRICS.record_search(id, name, elapsed, res.len());
+                    sink.complete(res);
+                }
+                Err(e) => {
+                    crate::PLUGIN_METRICS.record_panic(id, name, elapsed);
+                    sink.fail(panic_message(e.as_ref()));
+                }
+            }
+        });
+    }
+}
+
+pub type ExecuteJob = Box<dyn FnOnce() + Send>;
+
+// runs the jobs in order on one thread, so the window can close without waiting for them
+pub fn execute_in_order(jobs: Vec<ExecuteJob>) {
+    if jobs.is_empty() {
+        return;
+    }
+    if let Err(e) = std::thread::Builder::new().name("execute".to_string()).spawn(move || jobs.into_iter().for_each(|job| job())) {
+        LOGGER.error(&format!("failed to start executing the marked results: {}", e));
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+pub enum SearchEvent {
+    Results(Vec<quick_search_lib::SearchResult>),
+    Failed(String),
+}
+
+pub struct SearchMessage {
+    pub plugin: &'static str,
+    pub generation: u64,
+    pub event: SearchEvent,
+}
+
+// how a search on the pool reports back, every message is tagged with the plugin and the generation of the query it belongs to
+// this is not streaming: the plugin abi returns all results in one call, so a group gets exactly one message and a slow plugin still shows nothing until it is done
+// pushing partial batches needs a callback in quick_search_lib first, the generation tag only keeps results of an older query out of the window
+pub struct SearchSink {
+    plugin: &'static str,
+    generation: u64,
+    sender: crossbeam::channel::Sender<SearchMessage>,
+}
+
+impl SearchSink {
+    pub fn new(plugin: &'static str, generation: u64, sender: crossbeam::channel::Sender<SearchMessage>) -> Self {
+        Self { plugin, generation, sender }
+    }
+    fn send(&self, event: SearchEvent) {
+        if self
+            .sender
+            .send(SearchMessage {
+                plugin: self.plugin,
+                generation: self.generation,
+                event,
+            })
+            .is_err()
+        {
+            // the receiver is dropped when the search window closes, so this is expected for late searches
+            LOGGER.trace(&format!("search receiver for {} is gone", self.plugin));
+        }
+    }
+    pub fn complete(self, results: Vec<quick_search_lib::SearchResult>) {
+        self.send(SearchEvent::Results(results));
+    }
+    pub fn fail(self, message: String) {
+        self.send(SearchEvent::Failed(message));
     }
 }
 
@@ -284,5 +409,5 @@ pub struct SearchMetadata {
     pub priority: u32,
     pub raw_name: String,
     pub id: quick_search_lib::PluginId,
-    pub num_results: usize,
+    pub icon: Option<icons::ResultIcon>,
 }
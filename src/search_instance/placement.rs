@@ -0,0 +1,80 @@
+use egui_overlay::egui_window_glfw_passthrough::glfw::Monitor;
+
+use crate::config::MonitorPlacement;
+use crate::LOGGER;
+use quick_search_lib::Log;
+
+// the work area the overlay covers, in screen coordinates, which are negative for monitors left of or above the primary one
+pub struct Placement {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    // the monitor's content scale, egui is told to use it so the overlay is the same size on every monitor
+    pub scale: f32,
+}
+
+pub fn pick(monitors: &[Monitor], placement: MonitorPlacement, name: &str, focused: Option<(i32, i32)>) -> Option<Placement> {
+    let chosen = match placement {
+        MonitorPlacement::Primary => None,
+        MonitorPlacement::Mouse => cursor_pos().and_then(|point| monitor_at(monitors, point)),
+        MonitorPlacement::FocusedWindow => focused.and_then(|point| monitor_at(monitors, point)),
+        MonitorPlacement::Named => monitors.iter().find(|m| m.get_name().map(|n| n == name).unwrap_or(false)),
+    };
+    if chosen.is_none() && placement != MonitorPlacement::Primary {
+        LOGGER.info(&format!("no monitor found for placement {}, using the primary monitor", placement));
+    }
+    // glfw lists the primary monitor first
+    let monitor = chosen.or(monitors.first())?;
+    let (x, y, width, height) = monitor.get_workarea();
+    let (scale, _) = monitor.get_content_scale();
+    LOGGER.info(&format!("monitor {:?} workarea: {}x{} at {},{} scale {}", monitor.get_name(), width, height, x, y, scale));
+    Some(Placement {
+        x,
+        y,
+        width,
+        height,
+        scale: if scale > 0.0 { scale } else { 1.0 },
+    })
+}
+
+pub fn names(monitors: &[Monitor]) -> Vec<String> {
+    monitors.iter().filter_map(|m| m.get_name()).collect()
+}
+
+// a point on the taskbar is outside every work area, so the nearest work area wins
+fn monitor_at(monitors: &[Monitor], (px, py): (i32, i32)) -> Option<&Monitor> {
+    monitors.iter().min_by_key(|m| {
+        let (x, y, width, height) = m.get_workarea();
+        let dx = (x - px).max(px - (x + width - 1)).max(0) as i64;
+        let dy = (y - py).max(py - (y + height - 1)).max(0) as i64;
+        dx * dx + dy * dy
+    })
+}
+
+fn cursor_pos() -> Option<(i32, i32)> {
+    let mut point = winapi::shared::windef::POINT { x: 0, y: 0 };
+    match unsafe { winapi::um::winuser::GetCursorPos(&mut point) } {
+        0 => {
+            LOGGER.error("Failed to get the cursor position");
+            None
+        }
+        _ => Some((point.x, point.y)),
+    }
+}
+
+// the center of the window that had focus before the overlay opened, so this has to be called before the overlay window exists
+pub fn foreground_center() -> Option<(i32, i32)> {
+    unsafe {
+        let window = winapi::um::winuser::GetForegroundWindow();
+        if window.is_null() {
+            return None;
+        }
+        let mut rect = winapi::shared::windef::RECT { left: 0, top: 0, right: 0, bottom: 0 };
+        if winapi::um::winuser::GetWindowRect(window, &mut rect) == 0 {
+            LOGGER.error("Failed to get the focused window's position");
+            return None;
+        }
+        Some(((rect.left + rect.right) / 2, (rect.top + rect.bottom) / 2))
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+use crate::LOGGER;
+use quick_search_lib::Log;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// a shared pool of search threads, jobs are keyed by plugin so that each plugin only ever has one search running and one waiting
+// previews run on a separate pool of the same kind, so the cursor never waits on slow searches
+pub struct WorkerPool {
+    shared: Arc<Shared>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<PoolState>,
+    wake: Condvar,
+}
+
+#[derive(Default)]
+struct PoolState {
+    queue: VecDeque<(String, Job)>,
+    in_flight: HashSet<String>,
+    workers: usize,
+    target_workers: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoolStats {
+    pub workers: usize,
+    pub in_flight: usize,
+    pub queued: usize,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(e) => e.into_inner(),
+        }
+    }
+}
+
+// the thread count when the config doesn't set one
+pub const DEFAULT_WORKER_THREADS: usize = 4;
+
+impl Default for WorkerPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_WORKER_THREADS)
+    }
+}
+
+impl WorkerPool {
+    // the workers are started right away, so the first search doesn't wait for dispatch to size the pool
+    pub fn new(size: usize) -> Self {
+        let pool = Self { shared: Arc::default() };
+        pool.resize(size);
+        pool
+    }
+
+    // grows or shrinks the pool, extra workers exit once they are idle
+    pub fn resize(&self, size: usize) {
+        let size = size.max(1);
+        let mut state = self.shared.lock();
+        if state.target_workers == size {
+            return;
+        }
+        LOGGER.info(&format!("resizing search worker pool from {} to {}", state.target_workers, size));
+        state.target_workers = size;
+        while state.workers < state.target_workers {
+            state.workers += 1;
+            let shared = Arc::clone(&self.shared);
+            std::thread::spawn(move || worker(shared));
+        }
+        self.shared.wake.notify_all();
+    }
+
+    pub fn submit(&self, key: &str, job: impl FnOnce() + Send + 'static) {
+        let mut state = self.shared.lock();
+        if let Some(queued) = state.queue.iter_mut().find(|(k, _)| k == key) {
+            // a newer query for the same plugin replaces the one that never got to run
+            LOGGER.trace(&format!("replacing queued search for {}", key));
+            queued.1 = Box::new(job);
+        } else {
+            state.queue.push_back((key.to_string(), Box::new(job)));
+        }
+        self.shared.wake.notify_one();
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let state = self.shared.lock();
+        PoolStats {
+            workers: state.workers,
+            in_flight: state.in_flight.len(),
+            queued: state.queue.len(),
+        }
+    }
+}
+
+fn worker(shared: Arc<Shared>) {
+    let mut state = shared.lock();
+    loop {
+        if state.workers > state.target_workers {
+            state.workers -= 1;
+            LOGGER.trace("search worker exiting");
+            return;
+        }
+        // take the oldest job for a plugin that has nothing running right now
+        let next = state.queue.iter().position(|(key, _)| !state.in_flight.contains(key));
+        match next.and_then(|i| state.queue.remove(i)) {
+            Some((key, job)) => {
+                state.in_flight.insert(key.clone());
+                drop(state);
+                if std::panic::catch_unwind(std::panic::AssertUnwindSafe(job)).is_err() {
+                    LOGGER.error(&format!("search job for {} panicked", key));
+                }
+                state = shared.lock();
+                state.in_flight.remove(&key);
+                // a job for this plugin may have been waiting on this one
+                shared.wake.notify_all();
+            }
+            None => {
+                state = match shared.wake.wait(state) {
+                    Ok(state) => state,
+                    Err(e) => e.into_inner(),
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let start = Instant::now();
+        while !condition() {
+            assert!(start.elapsed() < Duration::from_secs(5), "timed out waiting for the pool");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn queued_job_is_replaced_by_a_newer_one_for_the_same_key() {
+        let pool = WorkerPool::new(2);
+        let (gate_sender, gate) = crossbeam::channel::bounded::<()>(0);
+        let (done_sender, done) = crossbeam::channel::unbounded();
+
+        let sender = done_sender.clone();
+        pool.submit("files", move || {
+            let _ = gate.recv();
+            let _ = sender.send(1);
+        });
+        wait_for(|| pool.stats().in_flight == 1);
+
+        // both wait behind the running search for the same plugin, only the newest one is kept
+        for n in [2, 3] {
+            let sender = done_sender.clone();
+            pool.submit("files", move || {
+                let _ = sender.send(n);
+            });
+        }
+        assert_eq!(pool.stats().queued, 1);
+
+        gate_sender.send(()).unwrap();
+        assert_eq!(done.recv_timeout(Duration::from_secs(5)), Ok(1));
+        assert_eq!(done.recv_timeout(Duration::from_secs(5)), Ok(3));
+        wait_for(|| pool.stats().in_flight == 0 && pool.stats().queued == 0);
+        assert!(done.try_recv().is_err());
+    }
+
+    #[test]
+    fn a_panicking_job_doesnt_take_its_worker_down() {
+        let pool = WorkerPool::new(1);
+        pool.submit("broken", || panic!("plugin bug"));
+        let (sender, done) = crossbeam::channel::bounded(1);
+        pool.submit("files", move || {
+            let _ = sender.send(());
+        });
+        assert!(done.recv_timeout(Duration::from_secs(5)).is_ok());
+        assert_eq!(pool.stats().workers, 1);
+    }
+
+    #[test]
+    fn workers_start_with_the_pool() {
+        assert_eq!(WorkerPool::new(3).stats().workers, 3);
+        assert_eq!(WorkerPool::default().stats().workers, DEFAULT_WORKER_THREADS);
+    }
+}
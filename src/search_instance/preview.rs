@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use quick_search_lib::SearchResult;
+
+use crate::LOGGER;
+use quick_search_lib::Log;
+
+use super::Plugin;
+
+// how much of a text file is read into the preview
+const PREVIEW_TEXT_BYTES: usize = 4096;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PreviewBlock {
+    Text(String),
+    Table(Vec<(String, String)>),
+    Markdown(String),
+    Image(PathBuf),
+}
+
+// previews are cached by the plugin and content of the result, so going back to a result doesn't build its preview again
+pub type PreviewKey = (&'static str, String, String);
+
+impl Plugin {
+    pub fn preview_key(&self, result: &SearchResult) -> PreviewKey {
+        (self.name, result.title().to_string(), result.context().to_string())
+    }
+
+    // the plugin abi has no preview call, so until quick-search-lib adds one the preview is built on the host from the result and whatever it points at on disk
+    // it runs on the preview pool under a single key, so a preview the cursor already moved away from is replaced before it runs
+    pub fn preview_pooled(&self, result: &SearchResult) -> crossbeam::channel::Receiver<Vec<PreviewBlock>> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        let result = result.clone();
+        let plugin_name = self.name;
+
+        crate::PREVIEW_POOL.submit("preview", move || {
+            if sender.send(build_preview(plugin_name, &result)).is_err() {
+                LOGGER.trace("preview receiver is gone");
+            }
+        });
+
+        receiver
+    }
+}
+
+fn build_preview(plugin_name: &str, result: &SearchResult) -> Vec<PreviewBlock> {
+    let mut blocks = vec![PreviewBlock::Text(result.title().to_string())];
+    if !result.context().is_empty() {
+        blocks.push(PreviewBlock::Text(result.context().to_string()));
+    }
+
+    let mut table = vec![("Source".to_string(), plugin_name.to_string())];
+
+    let path = [result.context(), result.title()].into_iter().map(PathBuf::from).find(|path| path.is_absolute() && path.exists());
+    if let Some(path) = path {
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            table.push(("Kind".to_string(), if metadata.is_dir() { "Folder".to_string() } else { "File".to_string() }));
+            if metadata.is_file() {
+                table.push(("Size".to_string(), format!("{} bytes", metadata.len())));
+            }
+            if let Ok(modified) = metadata.modified() {
+                table.push(("Modified".to_string(), chrono::DateTime::<chrono::Local>::from(modified).format("%Y-%m-%d %H:%M:%S").to_string()));
+            }
+        }
+
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match extension.as_str() {
+            "png" | "jpg" | "jpeg" | "bmp" | "gif" | "ico" | "webp" => blocks.push(PreviewBlock::Image(path)),
+            "md" | "markdown" => {
+                if let Some(text) = read_start(&path) {
+                    blocks.push(PreviewBlock::Markdown(text));
+                }
+            }
+            "txt" | "log" | "toml" | "json" | "rs" | "ini" | "cfg" => {
+                if let Some(text) = read_start(&path) {
+                    blocks.push(PreviewBlock::Text(text));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    blocks.insert(1, PreviewBlock::Table(table));
+    blocks
+}
+
+fn read_start(path: &std::path::Path) -> Option<String> {
+    use std::io::Read;
+
+    let mut buf = vec![0; PREVIEW_TEXT_BYTES];
+    let read = match std::fs::File::open(path).and_then(|mut f| f.read(&mut buf)) {
+        Ok(read) => read,
+        Err(e) => {
+            LOGGER.warn(&format!("Failed to read preview of {:?}: {}", path, e));
+            return None;
+        }
+    };
+    buf.truncate(read);
+    Some(String::from_utf8_lossy(&buf).into_owned())
+}
+
+// a very small subset of markdown, headings and bullet points, everything else is shown as plain text
+pub fn show_markdown(ui: &mut egui::Ui, text: &str) {
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some(heading) = trimmed.strip_prefix("# ") {
+            ui.label(egui::RichText::new(heading).heading());
+        } else if let Some(heading) = trimmed.strip_prefix("## ").or_else(|| trimmed.strip_prefix("### ")) {
+            ui.label(egui::RichText::new(heading).strong());
+        } else if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            ui.label(format!("• {}", item));
+        } else {
+            ui.label(line);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use quick_search_lib::SearchResult;
+
+// what the search bar input means once `@plugin`, `-@plugin` and `"phrases"` are picked out of it
+// anything else, `key:value` included, is plain text and reaches plugins as typed
+// key:value filters aren't parsed: the plugin abi only takes a string and has no way for a plugin to opt into them, so they wait on an upstream change
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    // the input exactly as typed
+    pub raw: String,
+    pub terms: Vec<QueryTerm>,
+    // plugins named with @, if any are given only those are searched
+    pub include: Vec<String>,
+    // plugins named with -@, never searched
+    pub exclude: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueryTerm {
+    Word(String),
+    Phrase(String),
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Self {
+        let mut query = SearchQuery {
+            raw: input.to_string(),
+            ..Default::default()
+        };
+
+        let mut chars = input.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let first = match chars.peek() {
+                Some(c) => *c,
+                None => break,
+            };
+
+            if first == '"' {
+                chars.next();
+                // an unterminated quote runs to the end of the input, so the phrase is usable while it is still being typed
+                let phrase = chars.by_ref().take_while(|c| *c != '"').collect::<String>();
+                if !phrase.trim().is_empty() {
+                    query.terms.push(QueryTerm::Phrase(phrase));
+                }
+                continue;
+            }
+
+            let mut token = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                token.push(c);
+            }
+
+            if let Some(name) = token.strip_prefix("-@") {
+                if !name.is_empty() {
+                    query.exclude.push(name.to_lowercase());
+                }
+            } else if let Some(name) = token.strip_prefix('@') {
+                if !name.is_empty() {
+                    query.include.push(name.to_lowercase());
+                }
+            } else {
+                query.terms.push(QueryTerm::Word(token));
+            }
+        }
+
+        query
+    }
+
+    // true if the text plugins would be sent is blank
+    pub fn is_empty(&self) -> bool {
+        self.text().trim().is_empty()
+    }
+
+    // what plugins are sent and what ranking and history use
+    // the input exactly as typed unless it names plugins or quotes a phrase, then the words and phrases without quotes
+    pub fn text(&self) -> String {
+        if self.include.is_empty() && self.exclude.is_empty() && !self.raw.contains('"') {
+            return self.raw.clone();
+        }
+        self.terms
+            .iter()
+            .map(|t| match t {
+                QueryTerm::Word(word) => word.as_str(),
+                QueryTerm::Phrase(phrase) => phrase.as_str(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn searches_plugin(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        if self.exclude.iter().any(|e| name.starts_with(e.as_str())) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(|i| name.starts_with(i.as_str()))
+    }
+
+    // phrases have to appear as written in the title or context, no matter what the plugin matched on
+    pub fn matches_phrases(&self, result: &SearchResult) -> bool {
+        self.terms.iter().all(|t| match t {
+            QueryTerm::Phrase(phrase) => {
+                let phrase = phrase.to_lowercase();
+                result.title().to_lowercase().contains(&phrase) || result.context().to_lowercase().contains(&phrase)
+            }
+            QueryTerm::Word(_) => true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_out_plugins_and_phrases() {
+        let query = SearchQuery::parse(r#"@Files -@web notes "quick search" ext:md"#);
+        assert_eq!(query.include, vec!["files"]);
+        assert_eq!(query.exclude, vec!["web"]);
+        assert_eq!(
+            query.terms,
+            vec![
+                QueryTerm::Word("notes".to_string()),
+                QueryTerm::Phrase("quick search".to_string()),
+                QueryTerm::Word("ext:md".to_string())
+            ]
+        );
+        assert_eq!(query.text(), "notes quick search ext:md");
+    }
+
+    #[test]
+    fn plain_input_is_sent_as_typed() {
+        assert_eq!(SearchQuery::parse("  Notes  ext:md ").text(), "  Notes  ext:md ");
+        assert_eq!(SearchQuery::parse("a@b.com").text(), "a@b.com");
+        assert_eq!(SearchQuery::parse(r#"@files  a   "b  c""#).text(), "a b  c");
+    }
+
+    #[test]
+    fn unterminated_phrase_runs_to_the_end() {
+        let query = SearchQuery::parse(r#"a "b c"#);
+        assert_eq!(query.terms, vec![QueryTerm::Word("a".to_string()), QueryTerm::Phrase("b c".to_string())]);
+    }
+
+    #[test]
+    fn only_plugin_names_is_empty() {
+        assert!(SearchQuery::parse("@files -@web").is_empty());
+        assert!(SearchQuery::parse(r#"  ""  "#).is_empty());
+        assert!(!SearchQuery::parse("@files x").is_empty());
+    }
+
+    #[test]
+    fn plugins_match_by_prefix() {
+        let query = SearchQuery::parse("@fi -@files-old x");
+        assert!(query.searches_plugin("Files"));
+        assert!(!query.searches_plugin("files-old"));
+        assert!(!query.searches_plugin("web"));
+        assert!(SearchQuery::parse("x").searches_plugin("web"));
+    }
+
+    #[test]
+    fn phrases_have_to_appear_in_the_result() {
+        let query = SearchQuery::parse(r#"notes "Quick Search""#);
+        assert!(query.matches_phrases(&SearchResult::new("quick search notes.txt")));
+        assert!(query.matches_phrases(&SearchResult::new("notes.txt").set_context("C:/quick search/notes.txt")));
+        assert!(!query.matches_phrases(&SearchResult::new("quick notes search.txt")));
+    }
+}
@@ -0,0 +1,163 @@
+use egui::{Color32, Rounding, Visuals};
+use serde::{Deserialize, Serialize};
+
+// colors are plain [r, g, b] so they read and edit nicely in config.toml
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    // the egui visuals everything not listed here is taken from
+    pub base: ThemeBase,
+    pub background: [u8; 3],
+    pub text: [u8; 3],
+    // the title of the result under the cursor, and selected text
+    pub selection: [u8; 3],
+    // the context of the result under the cursor
+    pub context: [u8; 3],
+    pub clock: [u8; 3],
+    // the result count and collapse arrow next to a plugin's name, the name itself is colored by the plugin
+    pub group_header: [u8; 3],
+    pub separator: [u8; 3],
+    // the check mark on results marked for batch execution
+    pub marked: [u8; 3],
+    // plugin status lines, like a search that timed out or panicked
+    pub warning: [u8; 3],
+    pub error: [u8; 3],
+    pub rounding: f32,
+    // how opaque window backgrounds are, text is always fully opaque
+    pub opacity: f32,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThemeBase {
+    Dark,
+    Light,
+}
+
+impl std::fmt::Display for ThemeBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeBase::Dark => write!(f, "Dark"),
+            ThemeBase::Light => write!(f, "Light"),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    // the colors the overlay always used
+    pub fn dark() -> Self {
+        Theme {
+            base: ThemeBase::Dark,
+            background: [27, 27, 27],
+            text: [140, 140, 140],
+            selection: [173, 216, 230],
+            context: [0, 128, 255],
+            clock: [255, 128, 128],
+            group_header: [140, 140, 140],
+            separator: [60, 60, 60],
+            marked: [144, 238, 144],
+            warning: [255, 255, 0],
+            error: [255, 0, 0],
+            rounding: 6.0,
+            opacity: 1.0,
+        }
+    }
+
+    pub fn light() -> Self {
+        Theme {
+            base: ThemeBase::Light,
+            background: [248, 248, 248],
+            text: [60, 60, 60],
+            selection: [0, 92, 197],
+            context: [0, 120, 160],
+            clock: [200, 40, 40],
+            group_header: [90, 90, 90],
+            separator: [190, 190, 190],
+            marked: [0, 140, 0],
+            warning: [180, 120, 0],
+            error: [200, 0, 0],
+            rounding: 6.0,
+            opacity: 1.0,
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Theme {
+            base: ThemeBase::Dark,
+            background: [0, 0, 0],
+            text: [255, 255, 255],
+            selection: [255, 255, 0],
+            context: [0, 255, 255],
+            clock: [255, 255, 255],
+            group_header: [255, 255, 255],
+            separator: [255, 255, 255],
+            marked: [0, 255, 0],
+            warning: [255, 255, 0],
+            error: [255, 0, 0],
+            rounding: 0.0,
+            opacity: 1.0,
+        }
+    }
+
+    pub fn presets() -> [(&'static str, Theme); 3] {
+        [("Dark", Theme::dark()), ("Light", Theme::light()), ("High contrast", Theme::high_contrast())]
+    }
+
+    pub fn visuals(&self) -> Visuals {
+        let mut visuals = match self.base {
+            ThemeBase::Dark => Visuals::dark(),
+            ThemeBase::Light => Visuals::light(),
+        };
+        visuals.popup_shadow.extrusion = 0.0;
+        visuals.window_shadow.extrusion = 0.0;
+
+        let [r, g, b] = self.background;
+        let alpha = (self.opacity.clamp(0.0, 1.0) * 255.0) as u8;
+        visuals.window_fill = Color32::from_rgba_unmultiplied(r, g, b, alpha);
+        visuals.window_stroke.color = color(self.separator);
+        visuals.widgets.noninteractive.bg_stroke.color = color(self.separator);
+        // labels, widgets keep their own colors so hovering still shows
+        visuals.widgets.noninteractive.fg_stroke.color = color(self.text);
+        visuals.selection.bg_fill = color(self.selection).gamma_multiply(0.4);
+        visuals.window_rounding = Rounding::same(self.rounding);
+        visuals.menu_rounding = Rounding::same(self.rounding);
+        visuals
+    }
+
+    pub fn selection(&self) -> Color32 {
+        color(self.selection)
+    }
+
+    pub fn context(&self) -> Color32 {
+        color(self.context)
+    }
+
+    pub fn clock(&self) -> Color32 {
+        color(self.clock)
+    }
+
+    pub fn group_header(&self) -> Color32 {
+        color(self.group_header)
+    }
+
+    pub fn marked(&self) -> Color32 {
+        color(self.marked)
+    }
+
+    pub fn warning(&self) -> Color32 {
+        color(self.warning)
+    }
+
+    pub fn error(&self) -> Color32 {
+        color(self.error)
+    }
+}
+
+fn color([r, g, b]: [u8; 3]) -> Color32 {
+    Color32::from_rgb(r, g, b)
+}